@@ -0,0 +1,247 @@
+use std::collections::{HashMap, HashSet};
+use crate::{DependencyKind, Graph, GraphError, TaskId, TaskStatus};
+
+/// A doable task along with the downstream work it unblocks, found by [`Graph::bottlenecks`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Bottleneck {
+    pub task_id: TaskId,
+    /// Number of tasks that become doable as soon as the task is finished.
+    pub direct: usize,
+    /// Tasks that become doable in turn once the task, and each task unblocked before them, is finished.
+    pub unblocked: Vec<TaskId>,
+    /// Sum of the estimates of the unblocked tasks, in seconds. Tasks without an estimate count as 0.
+    pub effort: u64,
+}
+
+/// A dependency implied by another chain of dependencies, found by [`Graph::redundant_dependencies`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RedundantDependency {
+    pub task_id: TaskId,
+    pub dependency_id: TaskId,
+    /// Longer chain going from the task to the dependency, making it redundant.
+    pub path: Vec<TaskId>,
+}
+
+impl Graph {
+
+    /// Dependencies that can be removed without changing which tasks depend on which, directly or not.
+    /// That is, a dependency of a task that is also reachable through another of its dependencies.
+    /// A hard dependency is only implied by a chain of hard dependencies, and a soft one by a chain of hard or soft ones.
+    /// Related links are never redundant.
+    /// Removing all of them at once leaves the transitive reduction of the graph.
+    pub fn redundant_dependencies(&self) -> Vec<RedundantDependency> {
+        let mut result = vec![];
+        for (task_id, task) in self.iter() {
+            for dependency_id in task.dependencies().iter().copied() {
+                let kind = task.dependency_kind(dependency_id);
+                if kind == DependencyKind::Related { continue }
+                let implies = |other_kind| other_kind == DependencyKind::Hard || other_kind == kind;
+                let path = task.dependencies()
+                    .iter()
+                    .copied()
+                    .filter(|other_id| *other_id != dependency_id && implies(task.dependency_kind(*other_id)))
+                    .find_map(|other_id| self.find_path_through(other_id, dependency_id, implies).ok().flatten());
+                if let Some(path) = path {
+                    let mut path_from_task = vec![task_id];
+                    path_from_task.extend(path);
+                    result.push(RedundantDependency { task_id, dependency_id, path: path_from_task });
+                }
+            }
+        }
+        result
+    }
+
+    /// Transitive dependents of a task that become doable in turn once it is finished,
+    /// as long as every task unblocked along the way is finished too.
+    /// Dependents also waiting on other unfinished tasks are left out.
+    pub fn unblocked_chain(&self, task_id: TaskId) -> Vec<TaskId> {
+        let mut finished = vec![task_id];
+        let mut i = 0;
+        while i < finished.len() {
+            let finished_id = finished[i];
+            i += 1;
+            for (dependent_id, dependent) in self.dependents(finished_id) {
+                if finished.contains(&dependent_id) { continue }
+                if dependent.dependency_kind(finished_id) != DependencyKind::Hard { continue }
+                if dependent.status.is_finished() || dependent.status == TaskStatus::Blocked { continue }
+                let unblocked = dependent
+                    .dependencies_of_kind(DependencyKind::Hard)
+                    .all(|dependency_id| {
+                        finished.contains(&dependency_id) ||
+                        self.get(dependency_id).is_none_or(|dependency| dependency.status.is_finished())
+                    });
+                if unblocked {
+                    finished.push(dependent_id);
+                }
+            }
+        }
+        finished.remove(0);
+        finished
+    }
+
+    /// Tasks along with their transitive dependencies that no other task needs, tasks first.
+    /// A dependency is needed by a task outside of those removed if it is a hard or soft dependency of it.
+    /// Tasks only related to the dependency don't count. Selected dependencies are always kept.
+    pub fn cascade(&self, task_ids: &[TaskId]) -> Result<Vec<TaskId>, GraphError> {
+        let mut result: Vec<TaskId> = vec![];
+        let mut removed = HashSet::new();
+        for task_id in task_ids.iter().copied() {
+            if self.get(task_id).is_none() { return Err(GraphError::TaskNotFound) }
+            if removed.insert(task_id) {
+                result.push(task_id);
+            }
+        }
+        // Number of dependents of each task that are not removed yet
+        let mut dependent_counts: HashMap<TaskId, usize> = HashMap::new();
+        for (_, task) in self.iter() {
+            for dependency_id in task.dependencies().iter().copied() {
+                if task.dependency_kind(dependency_id) == DependencyKind::Related { continue }
+                *dependent_counts.entry(dependency_id).or_default() += 1;
+            }
+        }
+        let mut i = 0;
+        while i < result.len() {
+            let task = self.get(result[i]).unwrap();
+            i += 1;
+            for dependency_id in task.dependencies().iter().copied() {
+                if task.dependency_kind(dependency_id) == DependencyKind::Related { continue }
+                let count = dependent_counts.get_mut(&dependency_id).unwrap();
+                *count -= 1;
+                if *count == 0 && !removed.contains(&dependency_id) && !self.get(dependency_id).is_some_and(|dependency| dependency.selected) {
+                    removed.insert(dependency_id);
+                    result.push(dependency_id);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Tasks unreachable from any selected task, ie: left out of the todo list.
+    pub fn orphans(&self) -> Vec<TaskId> {
+        let reachable: Vec<TaskId> = self.traverse_selected().into_iter().map(|(task_id, _)| task_id).collect();
+        self.iter()
+            .map(|(task_id, _)| task_id)
+            .filter(|task_id| !reachable.contains(task_id))
+            .collect()
+    }
+
+    /// Doable tasks that unblock the most downstream work, most first.
+    /// If `selected_only` is set, only doable tasks of the todo list are considered.
+    pub fn bottlenecks(&self, selected_only: bool) -> Vec<Bottleneck> {
+        let candidates: Vec<TaskId> = match selected_only {
+            true => self.traverse_selected().into_iter().map(|(task_id, _)| task_id).collect(),
+            false => self.iter().map(|(task_id, _)| task_id).collect(),
+        };
+        let mut bottlenecks: Vec<Bottleneck> = candidates
+            .into_iter()
+            .filter(|task_id| self.is_doable(*task_id))
+            .map(|task_id| {
+                let unblocked = self.unblocked_chain(task_id);
+                let effort = unblocked
+                    .iter()
+                    .filter_map(|unblocked_id| self.get(*unblocked_id)?.estimate)
+                    .sum();
+                Bottleneck { task_id, direct: self.unblocked_by(task_id).len(), unblocked, effort }
+            })
+            .collect();
+        bottlenecks.sort_by(|a, b| {
+            (b.unblocked.len(), b.effort)
+                .cmp(&(a.unblocked.len(), a.effort))
+                .then(a.task_id.cmp(&b.task_id))
+        });
+        bottlenecks
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{DependencyInfo, DependencyKind, Graph, RedundantDependency, Task, TaskStatus};
+
+    #[test]
+    fn test_bottlenecks() {
+        let mut graph = Graph::new();
+        let release_id = graph.insert(Task::new("Release"));
+        let package_id = graph.insert(Task::new("Package"));
+        let build_id = graph.insert(Task::new("Build"));
+        let docs_id = graph.insert(Task::new("Docs"));
+        let mut notes = Task::new("Release notes");
+        notes.estimate = Some(1800);
+        let notes_id = graph.insert(notes);
+        graph.insert_dependency(release_id, package_id).unwrap();
+        graph.insert_dependency(release_id, notes_id).unwrap();
+        graph.insert_dependency(package_id, build_id).unwrap();
+        graph.insert_dependency(notes_id, docs_id).unwrap();
+
+        // Release also waits on the notes, so building only unblocks packaging
+        assert_eq!(graph.unblocked_chain(build_id), &[package_id]);
+        graph.set_status(docs_id, TaskStatus::Done, 0).unwrap();
+        assert_eq!(graph.unblocked_chain(build_id), &[package_id]);
+        graph.set_status(notes_id, TaskStatus::Done, 0).unwrap();
+        assert_eq!(graph.unblocked_chain(build_id), &[package_id, release_id]);
+
+        graph.set_status(notes_id, TaskStatus::Todo, 0).unwrap();
+        let bottlenecks = graph.bottlenecks(false);
+        let summary: Vec<_> = bottlenecks.iter().map(|b| (b.task_id, b.direct, b.unblocked.len(), b.effort)).collect();
+        assert_eq!(summary, &[(build_id, 1, 1, 0), (notes_id, 0, 0, 0)]);
+        assert!(graph.bottlenecks(true).is_empty());
+    }
+
+    #[test]
+    fn test_redundant_dependencies() {
+        let mut graph = Graph::new();
+        let a = graph.insert(Task::new("A"));
+        let b = graph.insert(Task::new("B"));
+        let c = graph.insert(Task::new("C"));
+        let d = graph.insert(Task::new("D"));
+        graph.insert_dependency(a, b).unwrap();
+        graph.insert_dependency(b, c).unwrap();
+        graph.insert_dependency(c, d).unwrap();
+        graph.insert_dependency(a, c).unwrap();
+        graph.insert_dependency(a, d).unwrap();
+        graph.insert_dependency(b, d).unwrap();
+
+        let redundant = graph.redundant_dependencies();
+        assert_eq!(redundant, &[
+            RedundantDependency { task_id: a, dependency_id: c, path: vec![a, b, c] },
+            RedundantDependency { task_id: a, dependency_id: d, path: vec![a, b, c, d] },
+            RedundantDependency { task_id: b, dependency_id: d, path: vec![b, c, d] },
+        ]);
+        for dependency in redundant {
+            graph.remove_dependency(dependency.task_id, dependency.dependency_id).unwrap();
+        }
+        assert!(graph.redundant_dependencies().is_empty());
+        assert!(graph.is_reachable(a, d).unwrap());
+    }
+
+    #[test]
+    fn test_cascade_and_orphans() {
+        let mut graph = Graph::new();
+        let goal_id = graph.insert(Task::new("Goal"));
+        let design_id = graph.insert(Task::new("Design"));
+        let research_id = graph.insert(Task::new("Research"));
+        let shared_id = graph.insert(Task::new("Shared library"));
+        let other_id = graph.insert(Task::new("Other goal"));
+        let notes_id = graph.insert(Task::new("Notes"));
+        graph.insert_dependency(goal_id, research_id).unwrap();
+        graph.insert_dependency(goal_id, design_id).unwrap();
+        graph.insert_dependency(design_id, research_id).unwrap();
+        graph.insert_dependency(goal_id, shared_id).unwrap();
+        graph.insert_dependency(other_id, shared_id).unwrap();
+        graph.insert_dependency_with(other_id, research_id, DependencyInfo { kind: DependencyKind::Related, label: None }).unwrap();
+        graph.insert_dependency_with(goal_id, notes_id, DependencyInfo { kind: DependencyKind::Related, label: None }).unwrap();
+
+        // Research is only needed by tasks being removed, and related tasks are kept
+        assert_eq!(graph.cascade(&[goal_id]).unwrap(), &[goal_id, design_id, research_id]);
+        assert_eq!(graph.cascade(&[goal_id, other_id]).unwrap(), &[goal_id, other_id, design_id, shared_id, research_id]);
+        assert!(graph.cascade(&[42]).is_err());
+
+        // Dependencies on the todo list are kept
+        graph.set_selected(research_id, true).unwrap();
+        assert_eq!(graph.cascade(&[goal_id]).unwrap(), &[goal_id, design_id]);
+        graph.set_selected(research_id, false).unwrap();
+
+        graph.set_selected(goal_id, true).unwrap();
+        assert_eq!(graph.orphans(), &[other_id, notes_id]);
+    }
+}
@@ -0,0 +1,158 @@
+use std::fs;
+use slab::Slab;
+use serde::{Serialize, Deserialize};
+use ron::ser::PrettyConfig;
+use crate::{Config, DependencyKind, Graph, GraphError, Matcher, Task, TaskId, TaskMatch};
+
+/// Id of an entry in the [`Archive`].
+pub type ArchiveId = usize;
+
+/// Finished tasks moved out of the live graph, stored in a file next to it.
+/// Only loaded when needed, so the live graph stays small.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Archive {
+    entries: Slab<ArchivedTask>,
+}
+
+/// A task in the [`Archive`].
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ArchivedTask {
+    /// Id the task had in the live graph. May since have been reused.
+    pub task_id: TaskId,
+    /// The task, without its dependencies or parent.
+    pub task: Task,
+    /// Unix timestamp, in seconds, of when the task was archived.
+    pub archived_at: i64,
+}
+
+impl Archive {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, archive_id: ArchiveId) -> Option<&ArchivedTask> {
+        self.entries.get(archive_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ArchiveId, &ArchivedTask)> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Finds all archived tasks matching the matcher, most relevant first.
+    pub fn search(&self, matcher: &Matcher) -> Vec<(ArchiveId, &ArchivedTask, TaskMatch)> {
+        matcher.rank(self.iter(), |entry| &entry.task)
+    }
+
+    /// Moves an archived task back into the live graph.
+    /// Returns the new id of the task.
+    pub fn restore(&mut self, archive_id: ArchiveId, graph: &mut Graph) -> Result<TaskId, GraphError> {
+        let entry = self.entries.try_remove(archive_id).ok_or(GraphError::ArchivedTaskNotFound)?;
+        Ok(graph.insert(entry.task))
+    }
+
+    pub fn load(config: &Config) -> Result<Archive, GraphError> {
+        let archive_path = config.archive_path();
+        if fs::exists(&archive_path)? {
+            let archive_string = fs::read_to_string(&archive_path)?;
+            let archive = ron::de::from_str(&archive_string).map_err(|_| GraphError::ArchiveParseError)?;
+            Ok(archive)
+        }
+        else {
+            Ok(Archive::default())
+        }
+    }
+
+    pub fn save(&self, config: &Config) -> Result<(), GraphError> {
+        let archive_path = config.archive_path();
+        if let Some(parent_dir) = archive_path.parent() {
+            fs::create_dir_all(parent_dir)?;
+        }
+        let string = ron::ser::to_string_pretty(self, PrettyConfig::default()).expect("Failed to serialize archive");
+        fs::write(&archive_path, string)?;
+        Ok(())
+    }
+}
+
+impl Graph {
+
+    /// Finished tasks that can be archived, ie: those whose dependents are all finished too.
+    /// Tasks only related to them don't count.
+    /// If `min_age` is set, only tasks finished at least that many seconds before `now` are included.
+    /// Tasks finished without a recorded time are always old enough.
+    pub fn archivable(&self, min_age: Option<u64>, now: i64) -> Vec<TaskId> {
+        self.iter()
+            .filter(|(_, task)| task.status.is_finished())
+            .filter(|(_, task)| match (min_age, task.finished_at) {
+                (Some(min_age), Some(finished_at)) => now - finished_at >= min_age as i64,
+                _ => true,
+            })
+            .filter(|(task_id, _)| self
+                .dependents(*task_id)
+                .filter(|(_, dependent)| dependent.dependency_kind(*task_id) != DependencyKind::Related)
+                .all(|(_, dependent)| dependent.status.is_finished())
+            )
+            .map(|(task_id, _)| task_id)
+            .collect()
+    }
+
+    /// Moves archivable tasks into the archive. See [`Graph::archivable`].
+    /// Returns the ids the tasks had in the graph, paired with their ids in the archive.
+    pub fn archive(&mut self, archive: &mut Archive, min_age: Option<u64>, now: i64) -> Vec<(TaskId, ArchiveId)> {
+        let mut result = vec![];
+        for task_id in self.archivable(min_age, now) {
+            let Some(task) = self.remove(task_id) else { continue };
+            let archive_id = archive.entries.insert(ArchivedTask { task_id, task, archived_at: now });
+            result.push((task_id, archive_id));
+        }
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{Archive, Graph, Matcher, Task, TaskStatus};
+
+    #[test]
+    fn test_archive_and_restore() {
+        let mut graph = Graph::new();
+        let ship_id = graph.insert(Task::new("Ship"));
+        let build_id = graph.insert(Task::new("Build"));
+        let test_id = graph.insert(Task::new("Test"));
+        let lint_id = graph.insert(Task::new("Lint"));
+        graph.insert_dependency(ship_id, build_id).unwrap();
+        graph.insert_dependency(ship_id, test_id).unwrap();
+        graph.set_status(build_id, TaskStatus::Done, 1000).unwrap();
+        graph.set_status(test_id, TaskStatus::Done, 1000).unwrap();
+        graph.set_status(lint_id, TaskStatus::Cancelled, 5000).unwrap();
+
+        // Dependencies of an unfinished task stay, as do recently finished tasks
+        let mut archive = Archive::new();
+        assert!(graph.archive(&mut archive, Some(3600), 5000).is_empty());
+        graph.set_status(ship_id, TaskStatus::Done, 5000).unwrap();
+        let archived = graph.archive(&mut archive, Some(3600), 5000);
+        assert_eq!(archived.iter().map(|(task_id, _)| *task_id).collect::<Vec<_>>(), &[build_id, test_id]);
+        assert_eq!(graph.iter().count(), 2);
+        assert!(graph.get(ship_id).unwrap().dependencies().is_empty());
+        assert_eq!(graph.archive(&mut archive, None, 5000).len(), 2);
+        assert_eq!(graph.iter().count(), 0);
+
+        let results = archive.search(&Matcher::fuzzy("test", false));
+        let (archive_id, entry, _) = &results[0];
+        assert_eq!(entry.task_id, test_id);
+        let archive_id = *archive_id;
+        let task_id = archive.restore(archive_id, &mut graph).unwrap();
+        assert_eq!(graph.get(task_id).unwrap().name, "Test");
+        assert_eq!(archive.len(), 3);
+        assert!(archive.restore(archive_id, &mut graph).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::path::PathBuf;
+use clap::{Arg, CommandFactory, ValueEnum};
+use tdc::{Config, DependencyKind, Graph, TaskStatus};
+use crate::{Cli, TaskIds};
+
+/// Name of the hidden subcommand that shells call to complete a command line.
+pub const COMPLETE_COMMAND: &str = "__complete";
+
+/// Shells that completion scripts can be generated for.
+#[derive(ValueEnum, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+
+    /// Completion script to be sourced by the shell.
+    /// Each script delegates to `tdc __complete` so that task ids and tags are read from the current graph.
+    pub fn script(self) -> &'static str {
+        match self {
+            Shell::Bash => BASH_SCRIPT,
+            Shell::Zsh => ZSH_SCRIPT,
+            Shell::Fish => FISH_SCRIPT,
+        }
+    }
+}
+
+/// A single completion candidate, with an optional description.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Candidate {
+    pub value: String,
+    pub description: Option<String>,
+}
+
+impl Candidate {
+    fn new(value: impl Into<String>) -> Self {
+        Self { value: value.into(), description: None }
+    }
+
+    fn with_description(value: impl Into<String>, description: impl Into<String>) -> Self {
+        Self { value: value.into(), description: Some(description.into()) }
+    }
+}
+
+/// Prints completion candidates for a command line, one per line as `value<TAB>description`.
+/// `words` are the words following `tdc`, the last one being the word under the cursor.
+/// Failures are silently ignored since the output is consumed by a shell.
+pub fn print_candidates(words: &[String]) {
+    let Some(GlobalOptions { words, config_path, graph_path }) = GlobalOptions::parse(words) else { return };
+    let graph = env::current_dir()
+        .ok()
+        .and_then(|current_dir| Config::load_with(config_path.as_deref(), graph_path.as_deref(), &current_dir).ok())
+        .and_then(|config| Graph::load(&config).ok())
+        .unwrap_or_default();
+    for candidate in complete(&words, &graph) {
+        match candidate.description {
+            Some(description) => println!("{}\t{}", candidate.value, description),
+            None => println!("{}", candidate.value),
+        }
+    }
+}
+
+/// Global options found on a command line being completed.
+struct GlobalOptions {
+    /// Remaining words, with global options removed.
+    words: Vec<String>,
+    config_path: Option<PathBuf>,
+    graph_path: Option<PathBuf>,
+}
+
+impl GlobalOptions {
+
+    /// Separates global options from the other words of a command line.
+    /// Returns None if the word under the cursor is the value of a global option, which is left for the shell to complete.
+    fn parse(words: &[String]) -> Option<Self> {
+        let mut result = Self { words: vec![], config_path: None, graph_path: None };
+        let Some((current, previous)) = words.split_last() else { return Some(result) };
+        let mut previous = previous.iter();
+        while let Some(word) = previous.next() {
+            if word == "--config" {
+                result.config_path = Some(previous.next()?.into());
+            }
+            else if word == "--graph" {
+                result.graph_path = Some(previous.next()?.into());
+            }
+            else if let Some(path) = word.strip_prefix("--config=") {
+                result.config_path = Some(path.into());
+            }
+            else if let Some(path) = word.strip_prefix("--graph=") {
+                result.graph_path = Some(path.into());
+            }
+            else {
+                result.words.push(word.clone());
+            }
+        }
+        result.words.push(current.clone());
+        Some(result)
+    }
+}
+
+/// Computes completion candidates for a command line.
+/// `words` are the words following `tdc`, the last one being the word under the cursor.
+pub fn complete(words: &[String], graph: &Graph) -> Vec<Candidate> {
+    let (current, previous) = match words.split_last() {
+        Some((current, previous)) => (current.as_str(), previous),
+        None => ("", &[][..]),
+    };
+    let cli = Cli::command();
+
+    // Finds the innermost subcommand, completing subcommand names if there is none yet
+    let mut subcommand = &cli;
+    let mut args = previous;
+    while subcommand.has_subcommands() {
+        let Some((subcommand_name, rest)) = args.split_first() else {
+            return subcommand
+                .get_subcommands()
+                .filter(|subcommand| !subcommand.is_hide_set())
+                .map(|subcommand| {
+                    let about = subcommand.get_about().map(|about| about.to_string()).unwrap_or_default();
+                    Candidate::with_description(subcommand.get_name(), about)
+                })
+                .filter(|candidate| candidate.value.starts_with(current))
+                .collect();
+        };
+        let Some(found) = subcommand.find_subcommand(subcommand_name) else { return vec![] };
+        subcommand = found;
+        args = rest;
+    }
+
+    // Completes flags
+    if current.starts_with('-') && current != "-" {
+        let mut result = vec![];
+        for arg in subcommand.get_arguments().filter(|arg| !arg.is_positional()) {
+            let help = arg.get_help().map(|help| help.to_string()).unwrap_or_default();
+            if let Some(long) = arg.get_long() {
+                result.push(Candidate::with_description(format!("--{long}"), help.clone()));
+            }
+            if let Some(short) = arg.get_short() {
+                result.push(Candidate::with_description(format!("-{short}"), help));
+            }
+        }
+        result.retain(|candidate| candidate.value.starts_with(current));
+        return result;
+    }
+
+    // Finds the argument under the cursor, along with the positional values before it
+    let mut positional_values: Vec<(&Arg, &str)> = vec![];
+    let mut pending_option: Option<&Arg> = None;
+    for word in args {
+        if pending_option.take().is_some() { continue }
+        if word.starts_with('-') && word != "-" {
+            let option = subcommand.get_arguments().find(|arg| {
+                arg.get_long().is_some_and(|long| *word == format!("--{long}")) ||
+                arg.get_short().is_some_and(|short| *word == format!("-{short}"))
+            });
+            pending_option = option.filter(|option| option.get_action().takes_values());
+            continue;
+        }
+        let index = positional_values.len();
+        if let Some(arg) = positional_arg(subcommand, index) {
+            positional_values.push((arg, word.as_str()));
+        }
+    }
+    let arg = match pending_option {
+        Some(option) => option,
+        None => match positional_arg(subcommand, positional_values.len()) {
+            Some(arg) => arg,
+            None => return vec![],
+        },
+    };
+
+    // Completes values for the argument
+    let possible_values = arg.get_possible_values();
+    let mut result: Vec<Candidate> = if !possible_values.is_empty() {
+        possible_values
+            .iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| Candidate::new(value.get_name()))
+            .collect()
+    }
+    else {
+        match arg.get_id().as_str() {
+            "task_id" | "target_id" | "other_id" | "parent" | "task_ids" | "dependency_ids" | "before" | "after" => task_candidates(graph),
+            "tags" if subcommand.get_name() == "tagrm" => {
+                let task_ids = positional_values
+                    .iter()
+                    .filter(|(arg, _)| arg.get_id() == "task_ids")
+                    .filter_map(|(_, value)| value.parse::<TaskIds>().ok());
+                let mut tags = BTreeSet::new();
+                for task_ids in task_ids {
+                    for task_id in task_ids.expand(graph) {
+                        let Some(task) = graph.get(task_id) else { continue };
+                        tags.extend(task.tags().iter().cloned());
+                    }
+                }
+                tags.into_iter().map(Candidate::new).collect()
+            },
+            "tags" | "tag" | "target_tag" => {
+                let tags: BTreeSet<&str> = graph.tags().into_iter().collect();
+                tags.into_iter().map(Candidate::new).collect()
+            },
+            "trashed_ids" => graph
+                .trashed()
+                .iter()
+                .map(|trashed| Candidate::with_description(trashed.task_id.to_string(), trashed.task.name.clone()))
+                .collect(),
+            "kind" => DependencyKind::ALL.iter().map(|kind| Candidate::new(kind.as_str())).collect(),
+            "status" => TaskStatus::ALL.iter().map(|status| Candidate::new(status.as_str())).collect(),
+            _ => vec![],
+        }
+    };
+    result.retain(|candidate| candidate.value.starts_with(current));
+    result
+}
+
+/// Positional argument of a subcommand at a given index.
+/// Indices past the last positional argument map to it if it accepts multiple values.
+fn positional_arg(command: &clap::Command, index: usize) -> Option<&Arg> {
+    let positionals: Vec<&Arg> = command.get_positionals().collect();
+    if let Some(arg) = positionals.get(index) {
+        return Some(arg);
+    }
+    let last = positionals.last()?;
+    let multiple = last.get_num_args().is_some_and(|num_args| num_args.max_values() > 1);
+    multiple.then_some(*last)
+}
+
+fn task_candidates(graph: &Graph) -> Vec<Candidate> {
+    graph
+        .iter()
+        .map(|(task_id, task)| Candidate::with_description(task_id.to_string(), task.name.clone()))
+        .collect()
+}
+
+const BASH_SCRIPT: &str = r#"_tdc() {
+    local IFS=$'\n'
+    COMPREPLY=( $(tdc __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null | cut -f1) )
+}
+complete -o default -F _tdc tdc
+"#;
+
+const ZSH_SCRIPT: &str = r#"#compdef tdc
+_tdc() {
+    local -a candidates
+    local line value desc
+    for line in "${(@f)$(tdc __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}"; do
+        [[ -z "$line" ]] && continue
+        value="${line%%$'\t'*}"
+        value="${value//:/\\:}"
+        if [[ "$line" == *$'\t'* ]]; then
+            desc="${line#*$'\t'}"
+            candidates+=("$value:$desc")
+        else
+            candidates+=("$value")
+        fi
+    done
+    _describe 'tdc' candidates
+}
+compdef _tdc tdc
+"#;
+
+const FISH_SCRIPT: &str = r#"function __tdc_complete
+    set -l tokens (commandline -opc)
+    set -l current (commandline -ct)
+    tdc __complete -- $tokens[2..-1] $current 2>/dev/null
+end
+complete -c tdc -f -a '(__tdc_complete)'
+"#;
+
+
+#[cfg(test)]
+mod test {
+    use tdc::{Graph, Task};
+    use super::complete;
+
+    fn values(words: &[&str], graph: &Graph) -> Vec<String> {
+        let words: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+        complete(&words, graph).into_iter().map(|candidate| candidate.value).collect()
+    }
+
+    #[test]
+    fn test_complete() {
+        let mut graph = Graph::new();
+        let mut ship = Task::new("Ship");
+        ship.add_tag("release".into());
+        graph.insert(ship);
+        let mut build = Task::new("Build");
+        build.add_tag("ci".into());
+        graph.insert(build);
+
+        // Subcommands, then nested subcommands
+        assert_eq!(values(&["tag"], &graph), &["tags", "tag", "tagadd", "tagrm", "tagfind"]);
+        assert_eq!(values(&["template", "a"], &graph), &["apply"]);
+        assert!(values(&["nope", ""], &graph).is_empty());
+
+        // Flags, long and short
+        assert_eq!(values(&["rm", "--d"], &graph), &["--dry-run"]);
+        assert!(values(&["rm", "-"], &graph).is_empty());
+        assert_eq!(values(&["ls", "--"], &graph), &["--ids"]);
+
+        // Task ids, tags and values of options
+        let candidates = complete(&["rename".into(), "".into()], &graph);
+        assert_eq!(candidates.iter().map(|candidate| candidate.value.as_str()).collect::<Vec<_>>(), &["0", "1"]);
+        assert_eq!(candidates[1].description.as_deref(), Some("Build"));
+        assert!(values(&["rename", "0", ""], &graph).is_empty());
+        assert_eq!(values(&["tagadd", "0", ""], &graph), &["ci", "release"]);
+        assert_eq!(values(&["tagrm", "1", ""], &graph), &["ci"]);
+        assert_eq!(values(&["depadd", "0", "1", "--kind", "s"], &graph), &["soft"]);
+        assert_eq!(values(&["status", "0", "d"], &graph), &["done"]);
+    }
+}
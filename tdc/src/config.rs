@@ -1,58 +1,202 @@
 use std::{fs, env};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use ron::ser::PrettyConfig;
 use thiserror::Error;
 use serde::{Serialize, Deserialize};
+use crate::{Scoring, View};
 
 const APP_DIR_NAME: &str        = "tdc";
 const CONFIG_FILE_NAME: &str    = "config.ron";
 const GRAPH_FILE_NAME: &str     = "graph.ron";
+const PROJECT_DIR_NAME: &str    = ".tdc";
+const ARCHIVE_EXTENSION: &str   = "archive.ron";
+const TEMPLATES_FILE_NAME: &str = "templates.ron";
+
+/// Environment variable overriding the location of the config file.
+pub const CONFIG_ENV_VAR: &str  = "TDC_CONFIG";
+/// Environment variable overriding the location of the graph file.
+pub const GRAPH_ENV_VAR: &str   = "TDC_GRAPH";
 
 /// TodoChad application configuration
-#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug)]
-pub struct Config { pub graph_path: PathBuf }
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Config {
+    pub graph_path: PathBuf,
+    /// Named views, shown with `tdc view <name>`.
+    #[serde(default)]
+    pub views: BTreeMap<String, View>,
+    /// Weights used by `tdc next` to pick a task.
+    #[serde(default)]
+    pub scoring: Scoring,
+    /// Where `graph_path` came from.
+    #[serde(skip)]
+    pub graph_source: GraphSource,
+    /// Location of the config file itself.
+    #[serde(skip)]
+    pub path: PathBuf,
+}
 impl Config {
 
     /// Loads the config file from its standard location, creating it if it does not exist.
+    /// The location is `$TDC_CONFIG` if set, otherwise `$XDG_CONFIG_HOME/tdc/config.ron`, falling back to `$HOME/.config/tdc/config.ron`.
     pub fn load() -> Result<Self> {
-        let config_path = config_path()?;
-        if let Some(config_dir) = config_path.parent() {
-            fs::create_dir_all(config_dir)?;
+        Self::load_from(&config_path()?)
+    }
+
+    /// Loads a config file, creating it if it does not exist.
+    /// The graph path is overridden by `$TDC_GRAPH` if set.
+    pub fn load_from(config_path: &Path) -> Result<Self> {
+        let graph_override = env_path(GRAPH_ENV_VAR).map(|graph_path| (graph_path, GraphSource::Environment));
+        Self::load_overridden(config_path, graph_override)
+    }
+
+    /// Loads a config file, then overrides its graph path.
+    /// If the config file does not exist, it is only created when the graph isn't overridden,
+    /// so that the default graph path is never looked up for nothing.
+    fn load_overridden(config_path: &Path, graph_override: Option<(PathBuf, GraphSource)>) -> Result<Self> {
+        let mut config = if fs::exists(config_path)? {
+            let config_string = std::fs::read_to_string(config_path)?;
+            ron::de::from_str(&config_string).map_err(|_| ConfigError::ConfigParseError)?
         }
-        match fs::exists(&config_path) {
-            Ok(true) => {
-                let config_string = std::fs::read_to_string(&config_path)?;
-                ron::de::from_str(&config_string).map_err(|_| ConfigError::ConfigParseError)
-            },
-            Ok(false) => {
-                let graph_path = default_graph_path()?;
-                let config = Config { graph_path };
-                let config_string = ron::ser::to_string_pretty(&config, PrettyConfig::default()).expect("Failed to serialize config file");
-                fs::write(&config_path, config_string)?;
-                Ok(config)
+        else if let Some((graph_path, _)) = &graph_override {
+            Config::with_graph(graph_path.clone())
+        }
+        else {
+            if let Some(config_dir) = config_path.parent() {
+                fs::create_dir_all(config_dir)?;
+            }
+            let config = Config::with_graph(default_graph_path()?);
+            let config_string = ron::ser::to_string_pretty(&config, PrettyConfig::default()).expect("Failed to serialize config file");
+            fs::write(config_path, config_string)?;
+            config
+        };
+        config.path = config_path.to_owned();
+        if let Some((graph_path, graph_source)) = graph_override {
+            config.graph_path = graph_path;
+            config.graph_source = graph_source;
+        }
+        Ok(config)
+    }
+
+    /// Default config using a graph file.
+    fn with_graph(graph_path: PathBuf) -> Self {
+        Config {
+            graph_path,
+            views: BTreeMap::new(),
+            scoring: Scoring::default(),
+            graph_source: GraphSource::Config,
+            path: PathBuf::new(),
+        }
+    }
+
+    /// Loads the config file, applying overrides given on the command line.
+    /// The graph used is, in order of precedence: `graph_path`, `$TDC_GRAPH`, the nearest project graph found
+    /// by walking up from `project_dir`, and finally the graph path of the config file.
+    /// When the graph is overridden, a home directory is only needed to find the config file, and defaults are used without one.
+    pub fn load_with(config_path: Option<&Path>, graph_path: Option<&Path>, project_dir: &Path) -> Result<Self> {
+        let graph_override = match graph_path {
+            Some(graph_path) => Some((graph_path.to_owned(), GraphSource::CommandLine)),
+            None => env_path(GRAPH_ENV_VAR).map(|graph_path| (graph_path, GraphSource::Environment)),
+        };
+        let config_path = match config_path {
+            Some(config_path) => config_path.to_owned(),
+            None => match (self::config_path(), &graph_override) {
+                (Ok(config_path), _) => config_path,
+                (Err(ConfigError::HomeDirError), Some((graph_path, graph_source))) => {
+                    return Ok(Config { graph_source: *graph_source, ..Config::with_graph(graph_path.clone()) })
+                },
+                (Err(err), _) => return Err(err),
             },
-            Err(_) => todo!(),
+        };
+        let mut config = Self::load_overridden(&config_path, graph_override)?;
+        if config.graph_source == GraphSource::Config {
+            if let Some(graph_path) = find_project_graph(&std::path::absolute(project_dir)?) {
+                config.graph_path = graph_path;
+                config.graph_source = GraphSource::Project;
+            }
         }
+        Ok(config)
+    }
+
+    /// Location of the archive of the graph, next to the graph file, ie: `graph.archive.ron`.
+    pub fn archive_path(&self) -> PathBuf {
+        self.graph_path.with_extension(ARCHIVE_EXTENSION)
+    }
+
+    /// Location of the task templates, next to the config file, ie: `templates.ron`.
+    /// Shared by every graph using this config.
+    pub fn templates_path(&self) -> PathBuf {
+        self.path.with_file_name(TEMPLATES_FILE_NAME)
+    }
+}
+
+/// Origin of the graph path in a [`Config`].
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum GraphSource {
+    /// The config file.
+    #[default]
+    Config,
+    /// The `$TDC_GRAPH` environment variable.
+    Environment,
+    /// A project graph found in the working directory or one of its parents.
+    Project,
+    /// A command line flag.
+    CommandLine,
+}
+
+/// Location of the project graph in a directory, ie: `<dir>/.tdc/graph.ron`.
+pub fn project_graph_path(dir: &Path) -> PathBuf {
+    dir.join(PROJECT_DIR_NAME).join(GRAPH_FILE_NAME)
+}
+
+/// Finds the nearest project graph, starting at a directory and walking up through its parents.
+pub fn find_project_graph(start_dir: &Path) -> Option<PathBuf> {
+    start_dir
+        .ancestors()
+        .map(project_graph_path)
+        .find(|graph_path| graph_path.is_file())
+}
+
+/// Location of the config file.
+pub fn config_path() -> Result<PathBuf> {
+    if let Some(config_path) = env_path(CONFIG_ENV_VAR) {
+        return Ok(config_path);
     }
+    let config_dir = xdg_dir(env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME"), ".config")?;
+    Ok(config_dir.join(APP_DIR_NAME).join(CONFIG_FILE_NAME))
+}
 
+/// Location of the graph file used when creating a new config file.
+pub fn default_graph_path() -> Result<PathBuf> {
+    let data_dir = xdg_dir(env::var_os("XDG_DATA_HOME"), env::var_os("HOME"), ".local/share")?;
+    Ok(data_dir.join(APP_DIR_NAME).join(GRAPH_FILE_NAME))
 }
 
-fn config_path() -> Result<PathBuf> {
-    let home = env::var("HOME").map_err(|_| ConfigError::HomeDirError)?;
-    let config_path = format!("{home}/.config/{APP_DIR_NAME}/{CONFIG_FILE_NAME}");
-    Ok(PathBuf::from(config_path))
+/// Path stored in an environment variable, ignoring empty values.
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
 }
 
-fn default_graph_path() -> Result<PathBuf> {
-    let home = env::var("HOME").map_err(|_| ConfigError::HomeDirError)?;
-    let config_path = format!("{home}/.local/share/{APP_DIR_NAME}/{GRAPH_FILE_NAME}");
-    Ok(PathBuf::from(config_path))
+/// Resolves an XDG base directory.
+/// Uses the XDG variable if it holds an absolute path, as per the spec. Otherwise, falls back to a directory under home.
+fn xdg_dir(xdg_var: Option<OsString>, home_var: Option<OsString>, home_fallback: &str) -> Result<PathBuf> {
+    let xdg_dir = xdg_var.map(PathBuf::from).filter(|dir| dir.is_absolute());
+    if let Some(xdg_dir) = xdg_dir {
+        return Ok(xdg_dir);
+    }
+    let home = home_var
+        .filter(|home| !home.is_empty())
+        .ok_or(ConfigError::HomeDirError)?;
+    Ok(PathBuf::from(home).join(home_fallback))
 }
 
 
 #[derive(Error, Debug)]
 pub enum ConfigError {
-    #[error("Failed to get home directory")]
+    #[error("Failed to get home directory. Set HOME, XDG_CONFIG_HOME or TDC_CONFIG")]
     HomeDirError,
     #[error(transparent)]
     IOError(#[from] std::io::Error),
@@ -61,3 +205,48 @@ pub enum ConfigError {
 }
 
 type Result<T> = std::result::Result<T, ConfigError>;
+
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+    use std::path::PathBuf;
+    use super::{find_project_graph, project_graph_path, xdg_dir, Config, GraphSource};
+
+    #[test]
+    fn test_xdg_dir() {
+        let dir = xdg_dir(Some("/xdg/config".into()), Some("/home/foo".into()), ".config").unwrap();
+        assert_eq!(dir, PathBuf::from("/xdg/config"));
+        let dir = xdg_dir(None, Some("/home/foo".into()), ".config").unwrap();
+        assert_eq!(dir, PathBuf::from("/home/foo/.config"));
+        let dir = xdg_dir(Some("relative".into()), Some("/home/foo".into()), ".local/share").unwrap();
+        assert_eq!(dir, PathBuf::from("/home/foo/.local/share"));
+        assert!(xdg_dir(Some("".into()), None, ".config").is_err());
+    }
+
+    #[test]
+    fn test_find_project_graph() {
+        let root = env::temp_dir().join(format!("tdc_test_find_project_graph_{}", std::process::id()));
+        let nested = root.join("src").join("module");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_project_graph(&nested), None);
+        let graph_path = project_graph_path(&root);
+        fs::create_dir_all(graph_path.parent().unwrap()).unwrap();
+        fs::write(&graph_path, "").unwrap();
+        assert_eq!(find_project_graph(&nested), Some(graph_path.clone()));
+        assert_eq!(find_project_graph(&root), Some(graph_path));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_load_with_graph_override() {
+        let root = env::temp_dir().join(format!("tdc_test_load_with_graph_override_{}", std::process::id()));
+        let config_path = root.join("config.ron");
+        let graph_path = root.join("graph.ron");
+        let config = Config::load_with(Some(&config_path), Some(&graph_path), &root).unwrap();
+        assert_eq!(config.graph_path, graph_path);
+        assert_eq!(config.graph_source, GraphSource::CommandLine);
+        // The config file isn't created, as its graph path would be the default one
+        assert!(!root.exists());
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+use crate::{DependencyInfo, DependencyKind, Graph, TaskId, TaskOrder, TaskStatus};
+
+/// A difference between two graphs, found by [`Graph::diff`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Change {
+    pub task_id: TaskId,
+    /// Name of the task in the newer graph, or in the older one if it was removed.
+    pub name: String,
+    pub kind: ChangeKind,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Renamed { old_name: String },
+    Status { old: TaskStatus, new: TaskStatus },
+    TagAdded(String),
+    TagRemoved(String),
+    Order { old: TaskOrder, new: TaskOrder },
+    DependencyAdded { dependency_id: TaskId, dependency_name: String, kind: DependencyKind },
+    DependencyRemoved { dependency_id: TaskId, dependency_name: String },
+    /// Kind or label of a dependency changed.
+    DependencyChanged { dependency_id: TaskId, dependency_name: String, old: DependencyInfo, new: DependencyInfo },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (task_id, name) = (self.task_id, &self.name);
+        match &self.kind {
+            ChangeKind::Added => write!(f, "+ task {task_id}) {name}"),
+            ChangeKind::Removed => write!(f, "- task {task_id}) {name}"),
+            ChangeKind::Renamed { old_name } => write!(f, "~ name {task_id}) {old_name} -> {name}"),
+            ChangeKind::Status { old, new } => write!(f, "~ status {task_id}) {name}: {old} -> {new}"),
+            ChangeKind::TagAdded(tag) => write!(f, "+ tag {task_id}) {name}: {tag}"),
+            ChangeKind::TagRemoved(tag) => write!(f, "- tag {task_id}) {name}: {tag}"),
+            ChangeKind::Order { old, new } => write!(f, "~ order {task_id}) {name}: {} -> {}", display_order(*old), display_order(*new)),
+            ChangeKind::DependencyAdded { dependency_id, dependency_name, kind: DependencyKind::Hard } => {
+                write!(f, "+ dependency {task_id}) {name} -> {dependency_id}) {dependency_name}")
+            },
+            ChangeKind::DependencyAdded { dependency_id, dependency_name, kind } => {
+                write!(f, "+ dependency {task_id}) {name} -> {dependency_id}) {dependency_name} ({kind})")
+            },
+            ChangeKind::DependencyRemoved { dependency_id, dependency_name } => {
+                write!(f, "- dependency {task_id}) {name} -> {dependency_id}) {dependency_name}")
+            },
+            ChangeKind::DependencyChanged { dependency_id, dependency_name, old, new } => {
+                write!(f, "~ dependency {task_id}) {name} -> {dependency_id}) {dependency_name}: {} -> {}", display_info(old), display_info(new))
+            },
+        }
+    }
+}
+
+fn display_info(info: &DependencyInfo) -> String {
+    match &info.label {
+        Some(label) => format!("{}: {label}", info.kind),
+        None => info.kind.to_string(),
+    }
+}
+
+fn display_order(order: TaskOrder) -> String {
+    match order {
+        TaskOrder::Order(order) => order.to_string(),
+        TaskOrder::Last => "none".into(),
+    }
+}
+
+impl Graph {
+
+    /// Differences between an older graph and this one, task by task in order of id.
+    /// Tasks are matched by id. A task whose id was reused by a new task, ie: one created at another time,
+    /// shows up as removed then added, along with the dependencies on it.
+    pub fn diff(&self, old: &Graph) -> Vec<Change> {
+        let mut task_ids: Vec<TaskId> = old.iter().chain(self.iter()).map(|(task_id, _)| task_id).collect();
+        task_ids.sort();
+        task_ids.dedup();
+        let is_replaced = |task_id: TaskId| match (old.get(task_id), self.get(task_id)) {
+            (Some(old_task), Some(new_task)) => matches!(
+                (old_task.created_at, new_task.created_at),
+                (Some(old_created_at), Some(new_created_at)) if old_created_at != new_created_at
+            ),
+            _ => false,
+        };
+        let name_of = |task_id: TaskId| self.get(task_id)
+            .or_else(|| old.get(task_id))
+            .map(|task| task.name.clone())
+            .unwrap_or_default();
+        let old_name_of = |task_id: TaskId| match is_replaced(task_id) {
+            true => old.get(task_id).unwrap().name.clone(),
+            false => name_of(task_id),
+        };
+
+        let mut result = vec![];
+        for task_id in task_ids {
+            let mut push = |name: &str, kind| result.push(Change { task_id, name: name.to_owned(), kind });
+            let replaced = is_replaced(task_id);
+            let old_task = old.get(task_id);
+            if let Some(old_task) = old_task.filter(|_| replaced || self.get(task_id).is_none()) {
+                push(&old_task.name, ChangeKind::Removed);
+                for dependency_id in old_task.dependencies().iter().copied() {
+                    push(&old_task.name, ChangeKind::DependencyRemoved { dependency_id, dependency_name: old_name_of(dependency_id) });
+                }
+            }
+            let Some(new_task) = self.get(task_id) else { continue };
+            let old_task = old_task.filter(|_| !replaced);
+            let name = &new_task.name;
+            match old_task {
+                None => push(name, ChangeKind::Added),
+                Some(old_task) => {
+                    if old_task.name != new_task.name {
+                        push(name, ChangeKind::Renamed { old_name: old_task.name.clone() });
+                    }
+                    if old_task.status != new_task.status {
+                        push(name, ChangeKind::Status { old: old_task.status, new: new_task.status });
+                    }
+                    for tag in new_task.tags().iter().filter(|tag| !old_task.tags().contains(tag)) {
+                        push(name, ChangeKind::TagAdded(tag.clone()));
+                    }
+                    for tag in old_task.tags().iter().filter(|tag| !new_task.tags().contains(tag)) {
+                        push(name, ChangeKind::TagRemoved(tag.clone()));
+                    }
+                    if old_task.order != new_task.order {
+                        push(name, ChangeKind::Order { old: old_task.order, new: new_task.order });
+                    }
+                },
+            }
+            // Dependencies on a replaced task point to a different task, even though the id is the same
+            let old_dependencies = old_task.map(|old_task| old_task.dependencies()).unwrap_or_default();
+            let is_kept = |dependency_id: &TaskId| {
+                old_dependencies.contains(dependency_id) && new_task.dependencies().contains(dependency_id) && !is_replaced(*dependency_id)
+            };
+            for dependency_id in new_task.dependencies().iter().copied() {
+                let new_info = new_task.dependency_info(dependency_id).cloned().unwrap_or_default();
+                match old_task.filter(|_| is_kept(&dependency_id)) {
+                    None => push(name, ChangeKind::DependencyAdded { dependency_id, dependency_name: name_of(dependency_id), kind: new_info.kind }),
+                    Some(old_task) => {
+                        let old_info = old_task.dependency_info(dependency_id).cloned().unwrap_or_default();
+                        if old_info != new_info {
+                            push(name, ChangeKind::DependencyChanged { dependency_id, dependency_name: name_of(dependency_id), old: old_info, new: new_info });
+                        }
+                    },
+                }
+            }
+            for dependency_id in old_dependencies.iter().copied().filter(|dependency_id| !is_kept(dependency_id)) {
+                push(name, ChangeKind::DependencyRemoved { dependency_id, dependency_name: old_name_of(dependency_id) });
+            }
+        }
+        result
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{ChangeKind, DependencyInfo, DependencyKind, Graph, Task, TaskOrder, TaskStatus};
+
+    #[test]
+    fn test_diff() {
+        let mut old = Graph::new();
+        let ship_id = old.insert(Task::new("Ship"));
+        let build_id = old.insert(Task::new("Build"));
+        let mut docs = Task::new("Docs");
+        docs.created_at = Some(0);
+        let docs_id = old.insert(docs);
+        old.insert_dependency(ship_id, build_id).unwrap();
+        old.insert_dependency(ship_id, docs_id).unwrap();
+        old.get_mut(build_id).unwrap().add_tag("ci".into());
+        assert!(old.diff(&old).is_empty());
+
+        let mut new = old.clone();
+        new.remove(docs_id);
+        let test_id = new.insert(Task::new("Test"));
+        assert_eq!(test_id, docs_id);
+        new.get_mut(test_id).unwrap().created_at = Some(1000);
+        new.insert_dependency(ship_id, test_id).unwrap();
+        let build = new.get_mut(build_id).unwrap();
+        build.name = "Compile".into();
+        build.remove_tag("ci");
+        build.add_tag("build".into());
+        build.order = TaskOrder::Order(10);
+        new.set_status(build_id, TaskStatus::Done, 0).unwrap();
+
+        let kinds: Vec<ChangeKind> = new.diff(&old).into_iter().map(|change| change.kind).collect();
+        // Ship now depends on a different task with the same id
+        assert_eq!(kinds, &[
+            ChangeKind::DependencyAdded { dependency_id: test_id, dependency_name: "Test".into(), kind: DependencyKind::Hard },
+            ChangeKind::DependencyRemoved { dependency_id: docs_id, dependency_name: "Docs".into() },
+            ChangeKind::Renamed { old_name: "Build".into() },
+            ChangeKind::Status { old: TaskStatus::Todo, new: TaskStatus::Done },
+            ChangeKind::TagAdded("build".into()),
+            ChangeKind::TagRemoved("ci".into()),
+            ChangeKind::Order { old: TaskOrder::Last, new: TaskOrder::Order(10) },
+            ChangeKind::Removed,
+            ChangeKind::Added,
+        ]);
+
+        let soft = DependencyInfo { kind: DependencyKind::Soft, label: Some("nice to have".into()) };
+        new.insert_dependency_with(ship_id, build_id, soft).unwrap();
+        let changes = new.diff(&old);
+        assert_eq!(changes[0].to_string(), "~ dependency 0) Ship -> 1) Compile: hard -> soft: nice to have");
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt::Write;
+use crate::{Graph, GraphError, Task, TaskId, TaskOrder};
+
+const SEPARATOR: &str = " | ";
+/// Id given to lines of tasks to create.
+const NEW_TASK_ID: &str = "+";
+const HEADER: &str = "\
+# One task per line: id | name | tags | order | dependencies
+# Tags and dependencies are comma separated. Leave order empty for unordered tasks.
+# Remove a line to move its task to the trash. Use + as id to add a task.
+# Lines starting with # are ignored.
+";
+
+/// A line of the text edited by `tdc edit`. See [`render_tasks`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct EditedTask {
+    /// Line of the task in the text, starting at 1.
+    pub line: usize,
+    /// Id of the task, or None for a task to create.
+    pub task_id: Option<TaskId>,
+    pub name: String,
+    pub tags: Vec<String>,
+    pub order: TaskOrder,
+    pub dependencies: Vec<TaskId>,
+}
+
+/// Changes made by [`Graph::apply_edits`].
+#[derive(Clone, Eq, PartialEq, Default, Debug)]
+pub struct EditSummary {
+    pub added: Vec<TaskId>,
+    pub changed: Vec<TaskId>,
+    pub removed: Vec<TaskId>,
+}
+
+/// Renders tasks as text, one per line, to be edited then parsed with [`parse_edits`].
+pub fn render_tasks(graph: &Graph, task_ids: &[TaskId]) -> String {
+    let mut result = HEADER.to_owned();
+    for task_id in task_ids.iter().copied() {
+        let Some(task) = graph.get(task_id) else { continue };
+        let dependencies: Vec<String> = task.dependencies().iter().map(|dependency_id| dependency_id.to_string()).collect();
+        let fields = [task_id.to_string(), task.name.clone(), task.tags().join(","), task.order.to_string(), dependencies.join(",")];
+        writeln!(result, "{}", fields.join(SEPARATOR)).unwrap();
+    }
+    result
+}
+
+/// Parses text rendered by [`render_tasks`], after it was edited.
+/// Names may contain the separator, as the name is everything between the id and the last three fields.
+pub fn parse_edits(text: &str) -> Result<Vec<EditedTask>, GraphError> {
+    let mut result = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let invalid = |reason: String| GraphError::InvalidEditLine(line_number, reason);
+        if line.trim().is_empty() || line.trim_start().starts_with('#') { continue }
+        let fields: Vec<&str> = line.split('|').collect();
+        if fields.len() < 5 {
+            return Err(invalid("expected id | name | tags | order | dependencies".into()));
+        }
+        let (id, rest) = fields.split_first().unwrap();
+        let (name, rest) = rest.split_at(rest.len() - 3);
+        let [tags, order, dependencies] = rest else { unreachable!() };
+        let id = id.trim();
+        let task_id = match id {
+            NEW_TASK_ID => None,
+            _ => Some(id.parse().map_err(|_| invalid(format!("invalid task id '{id}'")))?),
+        };
+        let name = name.join("|").trim().to_owned();
+        if name.is_empty() {
+            return Err(invalid("name is empty".into()));
+        }
+        let order: TaskOrder = order.parse().map_err(|_| invalid(format!("invalid order '{}'", order.trim())))?;
+        let dependencies = split_list(dependencies)
+            .map(|dependency| dependency.parse().map_err(|_| invalid(format!("invalid dependency id '{dependency}'"))))
+            .collect::<Result<_, _>>()?;
+        let tags = split_list(tags).map(str::to_owned).collect();
+        result.push(EditedTask { line: line_number, task_id, name, tags, order, dependencies });
+    }
+    Ok(result)
+}
+
+/// Items of a comma separated list, trimmed, leaving out empty ones.
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|item| !item.is_empty())
+}
+
+impl Graph {
+
+    /// Applies edits made to the text of tasks rendered by [`render_tasks`].
+    /// `task_ids` are the tasks that were rendered: those missing from `edits` are moved to the trash.
+    /// Dependencies that were kept keep their kind and label, and new ones are hard.
+    /// Fails without changing the graph if an edit is invalid or would form a cycle.
+    pub fn apply_edits(&mut self, task_ids: &[TaskId], edits: &[EditedTask], now: i64) -> Result<EditSummary, GraphError> {
+        let mut edited_ids = vec![];
+        for edit in edits {
+            let Some(task_id) = edit.task_id else { continue };
+            let invalid = |reason: String| GraphError::InvalidEditLine(edit.line, reason);
+            if !task_ids.contains(&task_id) {
+                return Err(invalid(format!("task {task_id} was not part of the tasks edited")));
+            }
+            if edited_ids.contains(&task_id) {
+                return Err(invalid(format!("task {task_id} appears more than once")));
+            }
+            edited_ids.push(task_id);
+        }
+        let removed: Vec<TaskId> = task_ids
+            .iter()
+            .copied()
+            .filter(|task_id| self.get(*task_id).is_some() && !edited_ids.contains(task_id))
+            .collect();
+        for edit in edits {
+            for dependency_id in edit.dependencies.iter().copied() {
+                if self.get(dependency_id).is_none() || removed.contains(&dependency_id) {
+                    return Err(GraphError::InvalidEditLine(edit.line, format!("unknown dependency {dependency_id}")));
+                }
+            }
+        }
+
+        // Works on a copy, so that an invalid edit leaves the graph untouched
+        let mut graph = self.clone();
+        let mut summary = EditSummary::default();
+        for task_id in removed.iter().copied() {
+            graph.trash(task_id, now)?;
+        }
+        summary.removed = removed;
+        let mut edited: Vec<(TaskId, &EditedTask)> = vec![];
+        for edit in edits {
+            let task_id = match edit.task_id {
+                Some(task_id) => task_id,
+                None => {
+                    let mut task = Task::new(edit.name.clone());
+                    task.created_at = Some(now);
+                    let task_id = graph.insert(task);
+                    summary.added.push(task_id);
+                    task_id
+                },
+            };
+            let before = graph.get(task_id).unwrap().clone();
+            let task = graph.get_mut(task_id).unwrap();
+            task.name = edit.name.clone();
+            task.order = edit.order;
+            for tag in before.tags() {
+                task.remove_tag(tag);
+            }
+            for tag in &edit.tags {
+                task.add_tag(tag.clone());
+            }
+            // Dependencies are removed before any is added, so that moving one around never looks like a cycle
+            for dependency_id in before.dependencies().iter().copied() {
+                if !edit.dependencies.contains(&dependency_id) {
+                    graph.remove_dependency(task_id, dependency_id)?;
+                }
+            }
+            if edit.task_id.is_some() && !same_task(&before, edit) {
+                summary.changed.push(task_id);
+            }
+            edited.push((task_id, edit));
+        }
+        for (task_id, edit) in edited {
+            for dependency_id in edit.dependencies.iter().copied() {
+                let info = graph.get(task_id).unwrap().dependency_info(dependency_id).cloned().unwrap_or_default();
+                graph.insert_dependency_with(task_id, dependency_id, info)
+                    .map_err(|error| GraphError::InvalidEditLine(edit.line, error.to_string()))?;
+            }
+        }
+        *self = graph;
+        Ok(summary)
+    }
+}
+
+/// True if an edit leaves a task as it was.
+fn same_task(task: &Task, edit: &EditedTask) -> bool {
+    task.name == edit.name &&
+    task.order == edit.order &&
+    task.tags() == edit.tags &&
+    task.dependencies().len() == edit.dependencies.len() &&
+    task.dependencies().iter().all(|dependency_id| edit.dependencies.contains(dependency_id))
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use crate::{parse_edits, render_tasks, DependencyInfo, DependencyKind, Graph, GraphError, Task, TaskOrder};
+
+    #[test]
+    fn test_edit() {
+        let mut graph = Graph::new();
+        let ship_id = graph.insert(Task::new("Ship"));
+        let build_id = graph.insert(Task::new("Build"));
+        let docs_id = graph.insert(Task::new("Docs"));
+        graph.insert_dependency(ship_id, build_id).unwrap();
+        graph.insert_dependency_with(ship_id, docs_id, DependencyInfo { kind: DependencyKind::Soft, label: None }).unwrap();
+        let task_ids = [ship_id, build_id, docs_id];
+
+        let text = render_tasks(&graph, &task_ids);
+        assert!(text.contains("0 | Ship |  |  | 1,2"));
+        let edits = parse_edits(&text).unwrap();
+        assert_eq!(graph.clone().apply_edits(&task_ids, &edits, 0).unwrap(), Default::default());
+
+        let text = text
+            .replace("0 | Ship |  |  | 1,2", "0 | Ship | release | 10 | 2")
+            .replace("1 | Build |  |  | \n", "+ | Test | ci | 20 | \n");
+        let edits = parse_edits(&text).unwrap();
+        let summary = graph.apply_edits(&task_ids, &edits, 0).unwrap();
+        assert_eq!(summary.removed, &[build_id]);
+        assert_eq!(summary.changed, &[ship_id]);
+        let test_id = summary.added[0];
+        let ship = graph.get(ship_id).unwrap();
+        assert_eq!(ship.tags(), &["release"]);
+        assert_eq!(ship.order, TaskOrder::Order(10));
+        assert_eq!(ship.dependencies(), &[docs_id]);
+        assert_eq!(graph.get(test_id).unwrap().tags(), &["ci"]);
+        assert_eq!(ship.dependency_kind(docs_id), DependencyKind::Soft);
+        assert!(graph.is_trashed(build_id));
+
+        // Invalid edits leave the graph untouched
+        let task_ids = [ship_id, docs_id, test_id];
+        let edits = parse_edits("2 | Docs |  |  | 0").unwrap();
+        assert!(matches!(graph.apply_edits(&task_ids, &edits, 0), Err(GraphError::InvalidEditLine(1, _))));
+        let edits = parse_edits("42 | Nope |  |  | ").unwrap();
+        assert!(graph.apply_edits(&task_ids, &edits, 0).is_err());
+        assert!(parse_edits("0 | Ship | | x | ").is_err());
+        assert_eq!(graph.get(docs_id).unwrap().dependencies(), &[]);
+        assert_eq!(parse_edits("0 | A | B | C |  |  | ").unwrap()[0].name, "A | B | C");
+    }
+}
@@ -2,11 +2,23 @@ use slab::Slab;
 use thiserror::*;
 use serde::{Serialize, Deserialize};
 use ron::ser::PrettyConfig;
-use std::{collections::HashSet, fmt, fs};
-use crate::Config;
+use std::{collections::{BTreeMap, HashSet}, fmt, fs};
+use std::path::Path;
+use std::str::FromStr;
+use crate::{Config, TimeEntry, Timer, TrashedTask};
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
-pub struct Graph { tasks: Slab<Task> }
+pub struct Graph {
+    pub(crate) tasks: Slab<Task>,
+    /// Descriptions and colors of tags, keyed by lowercase tag name.
+    #[serde(default)]
+    pub(crate) tag_infos: BTreeMap<String, TagInfo>,
+    #[serde(default)]
+    pub(crate) timer: Option<Timer>,
+    /// Removed tasks that can still be restored. See [`Graph::trash`].
+    #[serde(default)]
+    pub(crate) trash: Vec<TrashedTask>,
+}
 impl Graph {
 
     pub fn new() -> Self {
@@ -14,20 +26,75 @@ impl Graph {
     }
 
     pub fn insert(&mut self, task: Task) -> TaskId {
-        self.tasks.insert(task)
+        // Ids of trashed tasks stay reserved
+        self.insert_first_vacant(task, |graph, task_id| !graph.is_trashed(task_id))
+    }
+
+    /// Inserts a task with a given id, which must be free.
+    pub(crate) fn insert_at(&mut self, task_id: TaskId, task: Task) -> Result<()> {
+        if self.tasks.contains(task_id) {
+            return Err(GraphError::TaskIdTaken(task_id));
+        }
+        self.insert_first_vacant(task, |_, vacant_id| vacant_id == task_id);
+        Ok(())
     }
 
+    /// Inserts a task at the first vacant id accepted.
+    /// The slab only hands out its next vacant id, so rejected ids are held by placeholders until one is accepted.
+    fn insert_first_vacant(&mut self, task: Task, accept: impl Fn(&Graph, TaskId) -> bool) -> TaskId {
+        let mut placeholder_ids = vec![];
+        while !accept(self, self.tasks.vacant_key()) {
+            placeholder_ids.push(self.tasks.insert(Task::default()));
+        }
+        let entry = self.tasks.vacant_entry();
+        let task_id = entry.key();
+        entry.insert(task);
+        // Freed in reverse, so the slab hands the held ids out again in the same order
+        for placeholder_id in placeholder_ids.into_iter().rev() {
+            self.tasks.remove(placeholder_id);
+        }
+        task_id
+    }
+
+    /// Permanently removes a task, along with the links other tasks have to it.
+    /// Its children are moved under its own parent. See [`Graph::trash`] to remove a task reversibly.
     pub fn remove(&mut self, task_id: TaskId) -> Option<Task> {
+        let task = self.remove_keeping_id(task_id)?;
+        // The id is free to be reused, so trashed tasks forget their links to it
+        for trashed in &mut self.trash {
+            trashed.dependencies.retain(|(dependency_id, _)| *dependency_id != task_id);
+            trashed.dependents.retain(|(dependent_id, _)| *dependent_id != task_id);
+            trashed.children.retain(|child_id| *child_id != task_id);
+            if trashed.parent == Some(task_id) {
+                trashed.parent = None;
+            }
+        }
+        Some(task)
+    }
+
+    /// Same as [`Graph::remove`], leaving links held by trashed tasks untouched.
+    pub(crate) fn remove_keeping_id(&mut self, task_id: TaskId) -> Option<Task> {
         let mut task = self.tasks.try_remove(task_id)?;
+        let parent_id = task.parent.take();
         task.dependencies.clear();
+        task.dependency_infos.clear();
+        if self.timer.is_some_and(|timer| timer.task_id == task_id) {
+            self.timer = None;
+        }
         for (_, t) in &mut self.tasks {
            t.dependencies.retain(|tid| *tid != task_id);
+           t.dependency_infos.remove(&task_id);
+           if t.parent == Some(task_id) {
+               t.parent = parent_id;
+           }
         }
         Some(task)
     }
 
     pub fn clear(&mut self) {
         self.tasks.clear();
+        self.timer = None;
+        self.trash.clear();
     }
 
     pub fn get(&self, task_id: TaskId) -> Option<&Task> {
@@ -48,6 +115,44 @@ impl Graph {
         result
     }
 
+    /// A task followed by all of its transitive dependencies, each visited once.
+    pub fn traverse_from(&self, task_id: TaskId) -> Result<Vec<(TaskId, &Task)>> {
+        if !self.contains_task(task_id) { return Err(GraphError::TaskNotFound) }
+        let mut result = vec![];
+        let mut visited = vec![false; self.tasks.capacity()];
+        self.traverse(task_id, &mut visited, &mut result);
+        Ok(result)
+    }
+
+    /// True if a task can be worked on now.
+    /// That is, it is neither finished nor blocked, and all of its hard dependencies are finished.
+    pub fn is_doable(&self, task_id: TaskId) -> bool {
+        let Some(task) = self.tasks.get(task_id) else { return false };
+        if task.status.is_finished() || task.status == TaskStatus::Blocked { return false }
+        task.dependencies_of_kind(DependencyKind::Hard)
+            .all(|dependency_id| self.tasks.get(dependency_id).is_none_or(|dependency| dependency.status.is_finished()))
+    }
+
+    /// Sets the status of a task.
+    /// Records `now` as the time the task was finished, if the status finishes it.
+    pub fn set_status(&mut self, task_id: TaskId, status: TaskStatus, now: i64) -> Result<()> {
+        let task = self.tasks.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
+        task.finished_at = match (task.status.is_finished(), status.is_finished()) {
+            (false, true) => Some(now),
+            (true, true) => task.finished_at,
+            (_, false) => None,
+        };
+        task.status = status;
+        Ok(())
+    }
+
+    /// Tasks that depend directly on a task.
+    pub fn dependents(&self, task_id: TaskId) -> impl Iterator<Item = (TaskId, &Task)> {
+        self.tasks
+            .iter()
+            .filter(move |(_, task)| task.dependencies.contains(&task_id))
+    }
+
     pub fn set_selected(&mut self, task_id: TaskId, selected: bool) -> Result<()> {
         let task = self.tasks.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
         task.selected = selected;
@@ -64,6 +169,81 @@ impl Graph {
         self.tasks.iter()
     }
 
+    /// Moves a task relative to another task, or to either end of the ordered tasks.
+    /// Picks an order between the task's new neighbours, renumbering ordered tasks if there is no room left.
+    /// Unordered tasks moved relative to are ordered last first.
+    pub fn reorder(&mut self, task_id: TaskId, position: OrderPosition) -> Result<()> {
+        if !self.contains_task(task_id) { return Err(GraphError::TaskNotFound) }
+        if let OrderPosition::Before(other_id) | OrderPosition::After(other_id) = position {
+            if other_id == task_id { return Ok(()) }
+            let other = self.tasks.get(other_id).ok_or(GraphError::TaskNotFound)?;
+            if other.order == TaskOrder::Last {
+                let mut ordered_ids = self.ordered_ids();
+                ordered_ids.retain(|id| *id != task_id);
+                let last_order = ordered_ids.last().map(|id| self.order_of(*id)).unwrap_or(0);
+                self.tasks[other_id].order = TaskOrder::Order(last_order.saturating_add(ORDER_STEP));
+            }
+        }
+        let mut ordered_ids = self.ordered_ids();
+        ordered_ids.retain(|id| *id != task_id);
+        let index = match position {
+            OrderPosition::Top => 0,
+            OrderPosition::Bottom => ordered_ids.len(),
+            OrderPosition::Before(other_id) => ordered_ids.iter().position(|id| *id == other_id).unwrap(),
+            OrderPosition::After(other_id) => ordered_ids.iter().position(|id| *id == other_id).unwrap() + 1,
+        };
+        let previous = index.checked_sub(1).map(|i| self.order_of(ordered_ids[i]) as i64);
+        let next = ordered_ids.get(index).map(|id| self.order_of(*id) as i64);
+        let step = ORDER_STEP as i64;
+        let order = match (previous, next) {
+            (None, None) => Some(step),
+            (Some(previous), None) => Some(previous + step),
+            (None, Some(next)) => Some(next - step),
+            (Some(previous), Some(next)) => (next - previous > 1).then(|| previous + (next - previous) / 2),
+        };
+        match order.and_then(|order| i32::try_from(order).ok()) {
+            Some(order) => self.tasks[task_id].order = TaskOrder::Order(order),
+            None => {
+                ordered_ids.insert(index, task_id);
+                self.renumber(&ordered_ids);
+            },
+        }
+        Ok(())
+    }
+
+    /// Renumbers ordered tasks to 10, 20, 30... keeping them in the same order.
+    pub fn normalize_orders(&mut self) {
+        let ordered_ids = self.ordered_ids();
+        self.renumber(&ordered_ids);
+    }
+
+    /// Ids of ordered tasks, sorted by order and then by id.
+    fn ordered_ids(&self) -> Vec<TaskId> {
+        let mut ordered: Vec<(i32, TaskId)> = self.tasks
+            .iter()
+            .filter_map(|(task_id, task)| match task.order {
+                TaskOrder::Order(order) => Some((order, task_id)),
+                TaskOrder::Last => None,
+            })
+            .collect();
+        ordered.sort();
+        ordered.into_iter().map(|(_, task_id)| task_id).collect()
+    }
+
+    /// Order of an ordered task.
+    fn order_of(&self, task_id: TaskId) -> i32 {
+        match self.tasks[task_id].order {
+            TaskOrder::Order(order) => order,
+            TaskOrder::Last => unreachable!("Task {task_id} is not ordered"),
+        }
+    }
+
+    fn renumber(&mut self, task_ids: &[TaskId]) {
+        for (i, task_id) in task_ids.iter().enumerate() {
+            self.tasks[*task_id].order = TaskOrder::Order((i as i32 + 1) * ORDER_STEP);
+        }
+    }
+
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (TaskId, &mut Task)> {
         self.tasks.iter_mut()
     }
@@ -84,6 +264,81 @@ impl Graph {
         result
     }
 
+    /// Each tag paired with the number of tasks using it, sorted by tag.
+    /// Tags are grouped case-insensitively, and only tasks with the exact tag are counted, not its sub-tags.
+    pub fn tag_counts(&self) -> Vec<(&str, usize)> {
+        let mut counts: BTreeMap<String, (&str, usize)> = BTreeMap::new();
+        for (_, task) in &self.tasks {
+            for tag in &task.tags {
+                let (_, count) = counts.entry(tag_key(tag)).or_insert((tag, 0));
+                *count += 1;
+            }
+        }
+        counts.into_values().collect()
+    }
+
+    /// Renames a tag and its sub-tags across all tasks, ie: renaming "work" to "job" also renames "work/backend" to "job/backend".
+    /// Fails if the new tag is already in use, unless only its case changes. Use [`Graph::merge_tag`] to combine two existing tags.
+    /// Returns the number of tasks modified.
+    pub fn rename_tag(&mut self, old: &str, new: &str) -> Result<usize> {
+        let case_only = tag_key(old.trim_end_matches(TAG_SEPARATOR)) == tag_key(new.trim_end_matches(TAG_SEPARATOR));
+        if !case_only && self.iter().any(|(_, task)| task.tags.iter().any(|tag| tag_matches(tag, new))) {
+            return Err(GraphError::TagExists);
+        }
+        self.retag_all(old, new)
+    }
+
+    /// Replaces a tag and its sub-tags with another across all tasks.
+    /// The description and color of the source tag are kept only if the target has none.
+    /// Fails if the target is a sub-tag of the source. Returns the number of tasks modified.
+    pub fn merge_tag(&mut self, source: &str, target: &str) -> Result<usize> {
+        if tag_key(source.trim_end_matches(TAG_SEPARATOR)) == tag_key(target.trim_end_matches(TAG_SEPARATOR)) { return Ok(0) }
+        if tag_matches(target, source) { return Err(GraphError::MergeIntoSubTag) }
+        self.retag_all(source, target)
+    }
+
+    /// Replaces the `source` part of every tag matching it, along with tag infos.
+    fn retag_all(&mut self, source: &str, target: &str) -> Result<usize> {
+        let mut modified = 0;
+        for (_, task) in &mut self.tasks {
+            let tags = std::mem::take(&mut task.tags);
+            let mut changed = false;
+            for tag in tags {
+                match retag(&tag, source, target) {
+                    Some(new_tag) => { task.add_tag(new_tag); changed = true },
+                    None => { task.add_tag(tag); },
+                }
+            }
+            if changed { modified += 1 }
+        }
+        if modified == 0 && !self.tag_infos.contains_key(&tag_key(source)) {
+            return Err(GraphError::TagNotFound);
+        }
+        let source_infos: Vec<String> = self.tag_infos
+            .keys()
+            .filter(|key| tag_matches(key, source))
+            .cloned()
+            .collect();
+        for key in source_infos {
+            let info = self.tag_infos.remove(&key).unwrap();
+            let new_key = tag_key(&retag(&key, source, target).unwrap());
+            let existing = self.tag_infos.entry(new_key).or_default();
+            existing.description = existing.description.take().or(info.description);
+            existing.color = existing.color.take().or(info.color);
+        }
+        Ok(modified)
+    }
+
+    /// Description and color of a tag, if any were set.
+    pub fn tag_info(&self, tag: &str) -> Option<&TagInfo> {
+        self.tag_infos.get(&tag_key(tag))
+    }
+
+    /// Mutable description and color of a tag, created if missing.
+    pub fn tag_info_mut(&mut self, tag: &str) -> &mut TagInfo {
+        self.tag_infos.entry(tag_key(tag)).or_default()
+    }
+
     pub fn len(&self) -> usize {
         self.tasks.len()
     }
@@ -92,27 +347,53 @@ impl Graph {
         self.tasks.is_empty()
     }
 
+    /// True if a task has soft dependencies that are not finished yet.
+    /// Such tasks are still doable, but should come after those dependencies.
+    pub fn waits_on_soft_dependencies(&self, task_id: TaskId) -> bool {
+        let Some(task) = self.tasks.get(task_id) else { return false };
+        task.dependencies_of_kind(DependencyKind::Soft)
+            .any(|dependency_id| self.tasks.get(dependency_id).is_some_and(|dependency| !dependency.status.is_finished()))
+    }
+
+    /// Inserts a hard dependency.
     pub fn insert_dependency(&mut self, task_id: TaskId, dependency_id: TaskId) -> Result<()> {
+        self.insert_dependency_with(task_id, dependency_id, DependencyInfo::default())
+    }
+
+    /// Inserts a dependency of any kind, or updates the kind and label of an existing one.
+    /// Dependencies of every kind are prevented from forming cycles.
+    pub fn insert_dependency_with(&mut self, task_id: TaskId, dependency_id: TaskId, info: DependencyInfo) -> Result<()> {
         if !self.contains_task(dependency_id) { return Err(GraphError::TaskNotFound) }
-        if self.is_reachable(dependency_id, task_id)? {
-            return Err(GraphError::CycleDetected);
+        if let Some(path) = self.find_path(dependency_id, task_id)? {
+            let mut cycle = vec![task_id];
+            cycle.extend(path);
+            return Err(GraphError::CycleDetected(cycle));
         }
         let task = self.tasks.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
-        if !task.dependencies.iter().any(|id| *id == dependency_id) {
+        if !task.dependencies.contains(&dependency_id) {
             task.dependencies.push(dependency_id);
         }
+        // Hard dependencies without a label are the default, and are not stored
+        if info == DependencyInfo::default() {
+            task.dependency_infos.remove(&dependency_id);
+        }
+        else {
+            task.dependency_infos.insert(dependency_id, info);
+        }
         Ok(())
     }
 
     pub fn remove_dependency(&mut self, task_id: TaskId, dependency_id: TaskId) -> Result<()> {
         let task = self.tasks.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
         task.dependencies.retain(|id| *id != dependency_id);
+        task.dependency_infos.remove(&dependency_id);
         Ok(())
     }
 
     pub fn clear_dependencies(&mut self, task_id: TaskId) -> Result<()> {
         let task = self.tasks.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
         task.dependencies.clear();
+        task.dependency_infos.clear();
         Ok(())
     }
 
@@ -120,39 +401,106 @@ impl Graph {
         self.tasks.get(task_id).is_some()
     }
 
-    /// Traverses the graph, starting at a given task.
-    /// Returns collection of tasks.
+    /// True if task b is task a, or one of its transitive dependencies.
     pub fn is_reachable(&self, task_id_a: TaskId, task_id_b: TaskId) -> Result<bool> {
+        Ok(self.find_path(task_id_a, task_id_b)?.is_some())
+    }
+
+    /// Finds a chain of dependencies going from task a to task b, both included.
+    /// Returns None if task b is not reachable from task a.
+    pub fn find_path(&self, task_id_a: TaskId, task_id_b: TaskId) -> Result<Option<Vec<TaskId>>> {
+        self.find_path_through(task_id_a, task_id_b, |_| true)
+    }
+
+    /// Same as [`Graph::find_path`], only following dependencies of the kinds accepted by `follow`.
+    pub fn find_path_through(&self, task_id_a: TaskId, task_id_b: TaskId, follow: impl Fn(DependencyKind) -> bool) -> Result<Option<Vec<TaskId>>> {
         // Early checks
-        let task_a = self.tasks.get(task_id_a).ok_or(GraphError::TaskNotFound)?;
-        if task_id_a == task_id_b { return Ok(true) }
-        if !self.contains_task(task_id_b) { return Err(GraphError::TaskNotFound) }
+        if !self.contains_task(task_id_a) || !self.contains_task(task_id_b) { return Err(GraphError::TaskNotFound) }
         // Main algorithm
         let mut visited = vec![false; self.tasks.capacity()];
-        visited[task_id_a] = true;
-        for dependency_id in task_a.dependencies.iter().copied() {
-            if self._is_reachable(dependency_id, task_id_b, &mut visited) {
-                return Ok(true)
-            }
+        let mut path = vec![];
+        if self._find_path(task_id_a, task_id_b, &follow, &mut visited, &mut path) {
+            return Ok(Some(path))
         }
-        Ok(false)
+        Ok(None)
     }
 
-    /// Traverses the graph, starting at a given task.
-    /// Returns collection of tasks.
-    pub fn _is_reachable(&self, task_id_a: TaskId, task_id_b: TaskId, visited: &mut [bool]) -> bool {
+    /// Finds a path from task a to task b, skipping visited tasks.
+    /// Leaves the path in `path` if found.
+    fn _find_path(
+        &self,
+        task_id_a: TaskId,
+        task_id_b: TaskId,
+        follow: &impl Fn(DependencyKind) -> bool,
+        visited: &mut [bool],
+        path: &mut Vec<TaskId>,
+    ) -> bool {
+        path.push(task_id_a);
         if task_id_a == task_id_b { return true }
-        let task_a = &self.tasks[task_id_a];
         visited[task_id_a] = true;
+        let task_a = &self.tasks[task_id_a];
         for dependency_id in task_a.dependencies.iter().copied() {
-            if visited[dependency_id] { continue }
-            if self._is_reachable(dependency_id, task_id_b, visited) {
+            if visited[dependency_id] || !follow(task_a.dependency_kind(dependency_id)) { continue }
+            if self._find_path(dependency_id, task_id_b, follow, visited, path) {
                 return true
             }
         }
+        path.pop();
         false
     }
 
+    /// Finds chains of dependencies going from task a to task b, both included, stopping after `limit` of them.
+    /// The number of chains can grow exponentially with the size of the graph.
+    pub fn find_all_paths(&self, task_id_a: TaskId, task_id_b: TaskId, limit: usize) -> Result<Vec<Vec<TaskId>>> {
+        if !self.contains_task(task_id_a) || !self.contains_task(task_id_b) { return Err(GraphError::TaskNotFound) }
+        // Only tasks reaching task b are explored, so that every branch leads to a chain
+        let mut dependents = vec![vec![]; self.tasks.capacity()];
+        for (task_id, task) in self.iter() {
+            for dependency_id in task.dependencies.iter().copied() {
+                dependents[dependency_id].push(task_id);
+            }
+        }
+        let mut reaching = vec![false; self.tasks.capacity()];
+        reaching[task_id_b] = true;
+        let mut stack = vec![task_id_b];
+        while let Some(task_id) = stack.pop() {
+            for dependent_id in dependents[task_id].iter().copied() {
+                if !reaching[dependent_id] {
+                    reaching[dependent_id] = true;
+                    stack.push(dependent_id);
+                }
+            }
+        }
+        let mut paths = vec![];
+        if reaching[task_id_a] && limit > 0 {
+            self._find_all_paths(task_id_a, task_id_b, &reaching, limit, &mut vec![], &mut paths);
+        }
+        Ok(paths)
+    }
+
+    fn _find_all_paths(
+        &self,
+        task_id_a: TaskId,
+        task_id_b: TaskId,
+        reaching: &[bool],
+        limit: usize,
+        path: &mut Vec<TaskId>,
+        paths: &mut Vec<Vec<TaskId>>,
+    ) {
+        path.push(task_id_a);
+        if task_id_a == task_id_b {
+            paths.push(path.clone());
+        }
+        else {
+            for dependency_id in self.tasks[task_id_a].dependencies.iter().copied() {
+                if paths.len() >= limit { break }
+                if !reaching[dependency_id] { continue }
+                self._find_all_paths(dependency_id, task_id_b, reaching, limit, path, paths);
+            }
+        }
+        path.pop();
+    }
+
     fn traverse<'a>(
         &'a self,
         task_id: TaskId,
@@ -164,27 +512,37 @@ impl Graph {
         let task = &self.tasks[task_id];
         result.push((task_id, task));
         for dependency_id in task.dependencies.iter().copied() {
+            if task.dependency_kind(dependency_id) == DependencyKind::Related { continue }
             self.traverse(dependency_id, visited, result);
         }
     }
 
     pub fn load(config: &Config) -> Result<Graph> {
         if fs::exists(&config.graph_path)? {
-            let graph_string = std::fs::read_to_string(&config.graph_path)?;
-            let graph = ron::de::from_str(&graph_string).map_err(|_| GraphError::GraphParseError)?;
-            Ok(graph)
+            Self::load_file(&config.graph_path)
         }
         else {
             Ok(Graph::default())
         }
     }
 
+    /// Loads a graph file, ie: a backup. Fails if the file does not exist.
+    pub fn load_file(path: &Path) -> Result<Graph> {
+        let graph_string = std::fs::read_to_string(path)?;
+        ron::de::from_str(&graph_string).map_err(|_| GraphError::GraphParseError)
+    }
+
     pub fn save(&self, config: &Config) -> Result<()> {
-        if let Some(parent_dir) = config.graph_path.parent() {
+        self.save_file(&config.graph_path)
+    }
+
+    /// Saves the graph to a file, creating its parent directories if needed.
+    pub fn save_file(&self, path: &Path) -> Result<()> {
+        if let Some(parent_dir) = path.parent() {
             fs::create_dir_all(parent_dir)?;
         }
         let string = ron::ser::to_string_pretty(self, PrettyConfig::default()).expect("Failed to serialize graph");
-        fs::write(&config.graph_path, string)?;
+        fs::write(path, string)?;
         Ok(())
     }
 }
@@ -193,12 +551,37 @@ impl Graph {
 pub struct Task {
     pub name: String,
     pub selected: bool,
+    #[serde(default)]
+    pub status: TaskStatus,
+    /// Unix timestamp, in seconds, of when the task was finished.
+    #[serde(default)]
+    pub finished_at: Option<i64>,
+    /// Unix timestamp, in seconds, of when the task was created. Unknown for tasks created before it was recorded.
+    #[serde(default)]
+    pub created_at: Option<i64>,
+    /// Unix timestamp, in seconds, of when the task is due.
+    #[serde(default)]
+    pub due: Option<i64>,
+    /// Estimated effort, in seconds.
+    #[serde(default)]
+    pub estimate: Option<u64>,
     pub order: TaskOrder,
     #[serde(default)]
     pub xy: Option<(f32, f32)>,
+    /// True if `todochad` hides the children of the task.
+    #[serde(default)]
+    pub collapsed: bool,
+    /// Task this task is grouped under, without depending on it. See [`Graph::set_parent`].
+    #[serde(default)]
+    pub(crate) parent: Option<TaskId>,
     #[serde(default)]
     tags: Vec<String>,
     dependencies: Vec<TaskId>,
+    /// Kinds and labels of dependencies, keyed by dependency id. Dependencies missing are hard and unlabeled.
+    #[serde(default)]
+    dependency_infos: BTreeMap<TaskId, DependencyInfo>,
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
 }
 
 impl Task {
@@ -207,10 +590,19 @@ impl Task {
         Self {
             name: name.into(),
             selected: false,
+            status: TaskStatus::default(),
+            finished_at: None,
+            created_at: None,
+            due: None,
+            estimate: None,
             order: TaskOrder::default(),
             xy: None,
+            collapsed: false,
+            parent: None,
             tags: vec![],
             dependencies: vec![],
+            dependency_infos: BTreeMap::new(),
+            time_entries: vec![],
         }
     }
 
@@ -228,9 +620,11 @@ impl Task {
         true
     }
 
+    /// True if the task has every tag specified, or one of its sub-tags.
+    /// IE: a task tagged "work/backend" has the tag "work".
     pub fn has_all_tags<'a>(&self, tags: impl IntoIterator<Item=&'a String>) -> bool {
         for tag in tags.into_iter() {
-            let has_tag = self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+            let has_tag = self.tags.iter().any(|t| tag_matches(t, tag));
             if !has_tag { 
                 return false
             }
@@ -252,6 +646,177 @@ impl Task {
     pub fn dependencies(&self) -> &[TaskId] {
         &self.dependencies
     }
+
+    pub fn parent(&self) -> Option<TaskId> {
+        self.parent
+    }
+
+    /// Copy of the task without its dependencies or parent.
+    pub(crate) fn without_links(&self) -> Task {
+        let mut task = self.clone();
+        task.parent = None;
+        task.dependencies.clear();
+        task.dependency_infos.clear();
+        task
+    }
+
+    /// Dependencies of a single kind.
+    pub fn dependencies_of_kind(&self, kind: DependencyKind) -> impl Iterator<Item = TaskId> + '_ {
+        self.dependencies
+            .iter()
+            .copied()
+            .filter(move |dependency_id| self.dependency_kind(*dependency_id) == kind)
+    }
+
+    /// Kind and label of a dependency.
+    pub fn dependency_info(&self, dependency_id: TaskId) -> Option<&DependencyInfo> {
+        self.dependency_infos.get(&dependency_id)
+    }
+
+    pub fn dependency_kind(&self, dependency_id: TaskId) -> DependencyKind {
+        self.dependency_info(dependency_id)
+            .map(|info| info.kind)
+            .unwrap_or_default()
+    }
+}
+
+/// Kind of a link between a task and one of its dependencies.
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Hash, Default, Debug)]
+pub enum DependencyKind {
+    /// The task can't be done until the dependency is finished.
+    #[default]
+    Hard,
+    /// The dependency should be done first, but doesn't hide the task from the todo list.
+    Soft,
+    /// The tasks are related, without either one waiting on the other.
+    Related,
+}
+
+impl DependencyKind {
+    pub const ALL: [DependencyKind; 3] = [DependencyKind::Hard, DependencyKind::Soft, DependencyKind::Related];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DependencyKind::Hard => "hard",
+            DependencyKind::Soft => "soft",
+            DependencyKind::Related => "related",
+        }
+    }
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for DependencyKind {
+    type Err = GraphError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "hard" | "blocks" => Ok(DependencyKind::Hard),
+            "soft" => Ok(DependencyKind::Soft),
+            "related" => Ok(DependencyKind::Related),
+            _ => Err(GraphError::InvalidDependencyKind(s.to_owned())),
+        }
+    }
+}
+
+/// Kind and label of a dependency.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Default, Debug)]
+pub struct DependencyInfo {
+    #[serde(default)]
+    pub kind: DependencyKind,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Separates levels of a hierarchical tag, ie: "work/backend".
+pub const TAG_SEPARATOR: char = '/';
+
+/// Tag folded to lowercase, to compare tags case-insensitively.
+/// Only ASCII letters are folded, so that the key has the same length as the tag.
+pub(crate) fn tag_key(tag: &str) -> String {
+    tag.to_ascii_lowercase()
+}
+
+/// True if a tag is equal to the filter, or is a sub-tag of it. Case-insensitive.
+/// IE: "work/backend" matches "work" and "work/backend", but not "work/back".
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    let filter = filter.trim_end_matches(TAG_SEPARATOR);
+    let Some(prefix) = tag.get(..filter.len()) else { return false };
+    if !prefix.eq_ignore_ascii_case(filter) { return false }
+    let rest = &tag[filter.len()..];
+    rest.is_empty() || rest.starts_with(TAG_SEPARATOR)
+}
+
+/// Replaces the `source` part of a tag with `target`, if the tag matches `source`.
+fn retag(tag: &str, source: &str, target: &str) -> Option<String> {
+    if !tag_matches(tag, source) { return None }
+    let source = source.trim_end_matches(TAG_SEPARATOR);
+    let target = target.trim_end_matches(TAG_SEPARATOR);
+    Some(format!("{target}{}", &tag[source.len()..]))
+}
+
+/// Optional metadata attached to a tag.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Default, Debug)]
+pub struct TagInfo {
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Color used to display the tag, as a hex string like "#ff8800".
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// Progress of a task.
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default, Debug)]
+pub enum TaskStatus {
+    #[default]
+    Todo,
+    InProgress,
+    /// Waiting on something outside of the graph.
+    Blocked,
+    Done,
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub const ALL: [TaskStatus; 5] = [TaskStatus::Todo, TaskStatus::InProgress, TaskStatus::Blocked, TaskStatus::Done, TaskStatus::Cancelled];
+
+    /// True if the task no longer needs work, and no longer holds back its dependents.
+    pub fn is_finished(self) -> bool {
+        matches!(self, TaskStatus::Done | TaskStatus::Cancelled)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "todo",
+            TaskStatus::InProgress => "in-progress",
+            TaskStatus::Blocked => "blocked",
+            TaskStatus::Done => "done",
+            TaskStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = GraphError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "todo" => Ok(TaskStatus::Todo),
+            "in-progress" | "inprogress" | "progress" | "doing" => Ok(TaskStatus::InProgress),
+            "blocked" | "waiting" => Ok(TaskStatus::Blocked),
+            "done" => Ok(TaskStatus::Done),
+            "cancelled" | "canceled" => Ok(TaskStatus::Cancelled),
+            _ => Err(GraphError::InvalidStatus(s.to_owned())),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Default, Debug)]
@@ -270,8 +835,41 @@ impl fmt::Display for TaskOrder {
     }
 }
 
+/// Parses an order as displayed: an integer, or nothing for [`TaskOrder::Last`].
+impl FromStr for TaskOrder {
+    type Err = GraphError;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "" => Ok(TaskOrder::Last),
+            order => order.parse().map(TaskOrder::Order).map_err(|_| GraphError::InvalidOrder(order.to_owned())),
+        }
+    }
+}
+
+/// Step between orders picked automatically by [`Graph::reorder`] and [`Graph::normalize_orders`].
+pub const ORDER_STEP: i32 = 10;
+
+/// Where to move a task with [`Graph::reorder`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum OrderPosition {
+    Before(TaskId),
+    After(TaskId),
+    /// Before all ordered tasks.
+    Top,
+    /// After all ordered tasks, but still before unordered ones.
+    Bottom,
+}
+
 pub type TaskId = usize;
 
+/// Formats a chain of task ids, ie: 3 -> 5 -> 7
+pub fn display_path(path: &[TaskId]) -> String {
+    path.iter()
+        .map(|task_id| task_id.to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
 #[derive(Error, Debug)]
 pub enum GraphError {
     #[error("Failed to parse graph file")]
@@ -282,8 +880,43 @@ pub enum GraphError {
     TaskNotFound,
     #[error("Task has unmet dependencies")]
     TaskDependenciesUnmet,
-    #[error("Cycle detected")]
-    CycleDetected,
+    /// Holds the chain of dependencies that would form the cycle, starting and ending with the same task.
+    #[error("Cycle detected: {}", display_path(.0))]
+    CycleDetected(Vec<TaskId>),
+    #[error("Tag not found")]
+    TagNotFound,
+    #[error("Tag already exists")]
+    TagExists,
+    #[error("Line {0}: {1}")]
+    InvalidEditLine(usize, String),
+    #[error("Template not found")]
+    TemplateNotFound,
+    #[error("Failed to parse templates file")]
+    TemplatesParseError,
+    #[error("Missing value for template variable '{0}'. Set it with {0}=<value>")]
+    MissingTemplateVariable(String),
+    #[error("A task can't be merged with itself")]
+    MergeIntoSelf,
+    #[error("Task not found in trash")]
+    TrashedTaskNotFound,
+    #[error("Archived task not found")]
+    ArchivedTaskNotFound,
+    #[error("Failed to parse archive file")]
+    ArchiveParseError,
+    #[error("Invalid dependency kind '{0}'. Expected hard, soft or related")]
+    InvalidDependencyKind(String),
+    #[error("Invalid status '{0}'. Expected todo, in-progress, blocked, done or cancelled")]
+    InvalidStatus(String),
+    #[error("Invalid order '{0}'")]
+    InvalidOrder(String),
+    #[error("A task can't be moved under itself or one of its descendants")]
+    InvalidParent,
+    #[error("A tag can't be merged into one of its sub-tags")]
+    MergeIntoSubTag,
+    #[error("Duration is too long")]
+    DurationTooLong,
+    #[error("Task id {0} is already taken")]
+    TaskIdTaken(TaskId),
 }
 
 type Result<T> = std::result::Result<T, GraphError>;
@@ -291,7 +924,7 @@ type Result<T> = std::result::Result<T, GraphError>;
 
 #[cfg(test)]
 mod test {
-    use crate::{ Graph, Task };
+    use crate::{ DependencyInfo, DependencyKind, Graph, GraphError, OrderPosition, Task, TaskOrder, TaskStatus };
 
     #[test]
     fn test_insertion_and_retrieval() {
@@ -302,6 +935,13 @@ mod test {
         let find_wallet = graph.get(find_wallet_id).unwrap();
         assert_eq!(find_keys, &Task::new("Find Keys"));
         assert_eq!(find_wallet, &Task::new("Find Wallet"));
+
+        // Inserting at a given id skips vacant ids before it, and never replaces a task
+        graph.insert_at(5, Task::new("Find Phone")).unwrap();
+        assert_eq!(graph.get(5).unwrap().name, "Find Phone");
+        assert!(matches!(graph.insert_at(find_keys_id, Task::new("Find Bag")), Err(GraphError::TaskIdTaken(0))));
+        assert_eq!(graph.get(find_keys_id).unwrap().name, "Find Keys");
+        assert_eq!(graph.insert(Task::new("Find Bag")), 2);
     }
 
     #[test]
@@ -332,4 +972,161 @@ mod test {
         let get_groceries = graph.get(get_groceries_id).unwrap();
         assert_eq!(get_groceries.dependencies, &[]);
     }
+
+    #[test]
+    fn test_hierarchical_tags() {
+        let mut graph = Graph::new();
+        let mut task = Task::new("Fix API");
+        task.add_tag("Work/Backend".into());
+        let task_id = graph.insert(task);
+        let task = graph.get(task_id).unwrap();
+        assert!(task.has_all_tags(&["work".to_owned()]));
+        assert!(task.has_all_tags(&["work/backend".to_owned()]));
+        assert!(!task.has_all_tags(&["work/back".to_owned()]));
+        assert!(!task.has_all_tags(&["backend".to_owned()]));
+    }
+
+    #[test]
+    fn test_rename_and_merge_tags() {
+        let mut graph = Graph::new();
+        let mut task = Task::new("Fix API");
+        task.add_tag("work/backend".into());
+        task.add_tag("urgent".into());
+        let fix_api_id = graph.insert(task);
+        let mut task = Task::new("Call boss");
+        task.add_tag("asap".into());
+        task.add_tag("urgent".into());
+        let call_boss_id = graph.insert(task);
+        graph.tag_info_mut("work").description = Some("Day job".into());
+
+        assert_eq!(graph.rename_tag("work", "job").unwrap(), 1);
+        assert_eq!(graph.get(fix_api_id).unwrap().tags(), &["job/backend", "urgent"]);
+        assert_eq!(graph.tag_info("job").unwrap().description.as_deref(), Some("Day job"));
+        assert!(graph.tag_info("work").is_none());
+        assert!(graph.rename_tag("asap", "urgent").is_err());
+        assert!(graph.rename_tag("missing", "other").is_err());
+
+        assert_eq!(graph.merge_tag("asap", "urgent").unwrap(), 1);
+        assert_eq!(graph.get(call_boss_id).unwrap().tags(), &["urgent"]);
+
+        // Only the case changes, and the tag keeps its info
+        assert_eq!(graph.rename_tag("job", "Job").unwrap(), 1);
+        assert_eq!(graph.get(fix_api_id).unwrap().tags(), &["Job/backend", "urgent"]);
+        assert!(graph.tag_info("job").is_some());
+        assert!(matches!(graph.merge_tag("job", "job/backend"), Err(GraphError::MergeIntoSubTag)));
+
+        // Non-ASCII letters are compared as is everywhere
+        graph.get_mut(call_boss_id).unwrap().add_tag("Équipe".into());
+        graph.get_mut(fix_api_id).unwrap().add_tag("équipe".into());
+        assert_eq!(graph.tag_counts().iter().filter(|(tag, _)| tag.ends_with("quipe")).count(), 2);
+        assert_eq!(graph.iter_with_tags(&["équipe".into()]).count(), 1);
+    }
+
+    #[test]
+    fn test_doable_respects_status() {
+        let mut graph = Graph::new();
+        let find_keys_id = graph.insert(Task::new("Find Keys"));
+        let find_wallet_id = graph.insert(Task::new("Find Wallet"));
+        let get_groceries_id = graph.insert(Task::new("Get Groceries"));
+        graph.insert_dependency(get_groceries_id, find_keys_id).unwrap();
+        graph.insert_dependency(get_groceries_id, find_wallet_id).unwrap();
+        assert!(graph.is_doable(find_keys_id));
+        assert!(!graph.is_doable(get_groceries_id));
+        graph.set_status(find_keys_id, TaskStatus::Done, 0).unwrap();
+        graph.set_status(find_wallet_id, TaskStatus::Cancelled, 0).unwrap();
+        assert!(!graph.is_doable(find_keys_id));
+        assert!(graph.is_doable(get_groceries_id));
+        graph.set_status(get_groceries_id, TaskStatus::Blocked, 0).unwrap();
+        assert!(!graph.is_doable(get_groceries_id));
+    }
+
+    #[test]
+    fn test_reorder() {
+        let mut graph = Graph::new();
+        let a = graph.insert(Task::new("A"));
+        let b = graph.insert(Task::new("B"));
+        let c = graph.insert(Task::new("C"));
+        let d = graph.insert(Task::new("D"));
+        let order = |graph: &Graph, task_id| graph.get(task_id).unwrap().order;
+
+        // Tasks moved relative to unordered tasks order them first
+        graph.reorder(b, OrderPosition::After(a)).unwrap();
+        assert_eq!((order(&graph, a), order(&graph, b)), (TaskOrder::Order(10), TaskOrder::Order(20)));
+        graph.reorder(c, OrderPosition::Before(b)).unwrap();
+        assert_eq!(order(&graph, c), TaskOrder::Order(15));
+        graph.reorder(d, OrderPosition::Top).unwrap();
+        assert_eq!(order(&graph, d), TaskOrder::Order(0));
+
+        // No room left between 10 and 11, so everything is renumbered
+        graph.get_mut(c).unwrap().order = TaskOrder::Order(11);
+        graph.reorder(b, OrderPosition::Before(c)).unwrap();
+        let orders: Vec<_> = [d, a, b, c].iter().map(|task_id| order(&graph, *task_id)).collect();
+        assert_eq!(orders, [10, 20, 30, 40].map(TaskOrder::Order));
+
+        graph.reorder(a, OrderPosition::Bottom).unwrap();
+        assert_eq!(order(&graph, a), TaskOrder::Order(50));
+        graph.normalize_orders();
+        let orders: Vec<_> = [d, b, c, a].iter().map(|task_id| order(&graph, *task_id)).collect();
+        assert_eq!(orders, [10, 20, 30, 40].map(TaskOrder::Order));
+
+        assert_eq!("".parse::<TaskOrder>().unwrap(), TaskOrder::Last);
+        assert_eq!(" -5 ".parse::<TaskOrder>().unwrap(), TaskOrder::Order(-5));
+        assert!(matches!("soon".parse::<TaskOrder>(), Err(GraphError::InvalidOrder(order)) if order == "soon"));
+    }
+
+    #[test]
+    fn test_paths_and_cycles() {
+        let mut graph = Graph::new();
+        let ship_id = graph.insert(Task::new("Ship"));
+        let build_id = graph.insert(Task::new("Build"));
+        let test_id = graph.insert(Task::new("Test"));
+        let fetch_id = graph.insert(Task::new("Fetch"));
+        graph.insert_dependency(ship_id, build_id).unwrap();
+        graph.insert_dependency(ship_id, test_id).unwrap();
+        graph.insert_dependency(build_id, fetch_id).unwrap();
+        graph.insert_dependency(test_id, fetch_id).unwrap();
+
+        assert_eq!(graph.find_path(ship_id, fetch_id).unwrap(), Some(vec![ship_id, build_id, fetch_id]));
+        assert_eq!(graph.find_path(fetch_id, ship_id).unwrap(), None);
+        assert_eq!(graph.find_all_paths(ship_id, fetch_id, 10).unwrap(), vec![
+            vec![ship_id, build_id, fetch_id],
+            vec![ship_id, test_id, fetch_id],
+        ]);
+        assert_eq!(graph.find_all_paths(ship_id, fetch_id, 1).unwrap(), vec![vec![ship_id, build_id, fetch_id]]);
+        assert!(graph.find_all_paths(fetch_id, ship_id, 10).unwrap().is_empty());
+        match graph.insert_dependency(fetch_id, ship_id) {
+            Err(GraphError::CycleDetected(cycle)) => assert_eq!(cycle, &[fetch_id, ship_id, build_id, fetch_id]),
+            result => panic!("Expected a cycle, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_dependency_kinds() {
+        let mut graph = Graph::new();
+        let ship_id = graph.insert(Task::new("Ship"));
+        let build_id = graph.insert(Task::new("Build"));
+        let review_id = graph.insert(Task::new("Review"));
+        let notes_id = graph.insert(Task::new("Notes"));
+        graph.get_mut(ship_id).unwrap().selected = true;
+        graph.insert_dependency(ship_id, build_id).unwrap();
+        let soft = DependencyInfo { kind: DependencyKind::Soft, label: Some("catch bugs early".into()) };
+        graph.insert_dependency_with(ship_id, review_id, soft.clone()).unwrap();
+        let related = DependencyInfo { kind: DependencyKind::Related, label: None };
+        graph.insert_dependency_with(ship_id, notes_id, related).unwrap();
+        assert_eq!(graph.get(ship_id).unwrap().dependency_info(review_id), Some(&soft));
+
+        // Soft dependencies are on the todo list without blocking, and related tasks are left out
+        let todo_ids: Vec<_> = graph.traverse_selected().into_iter().map(|(task_id, _)| task_id).collect();
+        assert_eq!(todo_ids, &[ship_id, build_id, review_id]);
+        graph.set_status(build_id, TaskStatus::Done, 0).unwrap();
+        assert!(graph.is_doable(ship_id));
+        assert!(graph.waits_on_soft_dependencies(ship_id));
+
+        // Related links still can't form cycles
+        assert!(graph.insert_dependency(notes_id, ship_id).is_err());
+
+        graph.insert_dependency(ship_id, review_id).unwrap();
+        assert_eq!(graph.get(ship_id).unwrap().dependency_kind(review_id), DependencyKind::Hard);
+        assert!(!graph.is_doable(ship_id));
+    }
 }
@@ -0,0 +1,130 @@
+use crate::{Graph, GraphError, Task, TaskId};
+
+/// Finished tasks among the descendants of a parent task, found by [`Graph::progress`].
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct Progress {
+    pub finished: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    /// Ratio of finished descendants, from 0 to 1. A task without children has a ratio of 0.
+    pub fn ratio(self) -> f64 {
+        if self.total == 0 { return 0.0 }
+        self.finished as f64 / self.total as f64
+    }
+}
+
+impl Graph {
+
+    /// Moves a task under a parent task, or back to the top level if `parent_id` is None.
+    /// Parents group their children without depending on them.
+    pub fn set_parent(&mut self, task_id: TaskId, parent_id: Option<TaskId>) -> Result<(), GraphError> {
+        if self.get(task_id).is_none() { return Err(GraphError::TaskNotFound) }
+        if let Some(parent_id) = parent_id {
+            if self.get(parent_id).is_none() { return Err(GraphError::TaskNotFound) }
+            if parent_id == task_id || self.ancestors(parent_id).contains(&task_id) {
+                return Err(GraphError::InvalidParent)
+            }
+        }
+        self.get_mut(task_id).unwrap().parent = parent_id;
+        Ok(())
+    }
+
+    /// Tasks directly under a task.
+    pub fn children(&self, task_id: TaskId) -> impl Iterator<Item = (TaskId, &Task)> {
+        self.iter().filter(move |(_, task)| task.parent == Some(task_id))
+    }
+
+    /// Tasks without a parent.
+    pub fn roots(&self) -> impl Iterator<Item = (TaskId, &Task)> {
+        self.iter().filter(|(_, task)| task.parent.is_none())
+    }
+
+    /// Parent of a task, followed by its parent and so on, up to the top level.
+    pub fn ancestors(&self, task_id: TaskId) -> Vec<TaskId> {
+        let mut result = vec![];
+        let mut current = self.get(task_id).and_then(|task| task.parent);
+        while let Some(parent_id) = current {
+            result.push(parent_id);
+            current = self.get(parent_id).and_then(|parent| parent.parent);
+        }
+        result
+    }
+
+    /// Children of a task, their children and so on, each child directly after its parent.
+    pub fn descendants(&self, task_id: TaskId) -> Vec<TaskId> {
+        let mut result = vec![];
+        for (child_id, _) in self.children(task_id) {
+            result.push(child_id);
+            result.extend(self.descendants(child_id));
+        }
+        result
+    }
+
+    /// Number of finished descendants of a task, out of all of them.
+    pub fn progress(&self, task_id: TaskId) -> Progress {
+        let descendants = self.descendants(task_id);
+        let finished = descendants
+            .iter()
+            .filter(|descendant_id| self.get(**descendant_id).is_some_and(|descendant| descendant.status.is_finished()))
+            .count();
+        Progress { finished, total: descendants.len() }
+    }
+
+    /// Every task paired with its depth in the hierarchy, top level tasks being at depth 0.
+    /// Children come directly after their parent, and siblings are kept in the order given by `key`.
+    pub fn hierarchy<K: Ord>(&self, key: impl Fn(TaskId, &Task) -> K) -> Vec<(TaskId, usize)> {
+        let mut result = vec![];
+        let mut roots: Vec<(TaskId, &Task)> = self.roots().collect();
+        roots.sort_by_key(|(task_id, task)| key(*task_id, task));
+        for (root_id, _) in roots {
+            self._hierarchy(root_id, 0, &key, &mut result);
+        }
+        result
+    }
+
+    fn _hierarchy<K: Ord>(&self, task_id: TaskId, depth: usize, key: &impl Fn(TaskId, &Task) -> K, result: &mut Vec<(TaskId, usize)>) {
+        result.push((task_id, depth));
+        let mut children: Vec<(TaskId, &Task)> = self.children(task_id).collect();
+        children.sort_by_key(|(child_id, child)| key(*child_id, child));
+        for (child_id, _) in children {
+            self._hierarchy(child_id, depth + 1, key, result);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{Graph, GraphError, Progress, Task, TaskStatus};
+
+    #[test]
+    fn test_hierarchy() {
+        let mut graph = Graph::new();
+        let epic_id = graph.insert(Task::new("Epic"));
+        let story_id = graph.insert(Task::new("Story"));
+        let subtask_id = graph.insert(Task::new("Subtask"));
+        let other_id = graph.insert(Task::new("Other"));
+        graph.set_parent(story_id, Some(epic_id)).unwrap();
+        graph.set_parent(subtask_id, Some(story_id)).unwrap();
+        assert!(matches!(graph.set_parent(epic_id, Some(subtask_id)), Err(GraphError::InvalidParent)));
+        assert!(matches!(graph.set_parent(epic_id, Some(epic_id)), Err(GraphError::InvalidParent)));
+
+        // Children are grouped under their parent without being dependencies
+        assert!(graph.get(epic_id).unwrap().dependencies().is_empty());
+        assert!(graph.is_doable(epic_id));
+        assert_eq!(graph.ancestors(subtask_id), &[story_id, epic_id]);
+        assert_eq!(graph.hierarchy(|task_id, _| task_id), &[(epic_id, 0), (story_id, 1), (subtask_id, 2), (other_id, 0)]);
+
+        graph.set_status(subtask_id, TaskStatus::Done, 0).unwrap();
+        assert_eq!(graph.progress(epic_id), Progress { finished: 1, total: 2 });
+        assert_eq!(graph.progress(other_id), Progress::default());
+
+        // Removing a task hands its children to its parent
+        graph.remove(story_id);
+        assert_eq!(graph.get(subtask_id).unwrap().parent(), Some(epic_id));
+        graph.set_parent(subtask_id, None).unwrap();
+        assert_eq!(graph.children(epic_id).count(), 0);
+    }
+}
@@ -1,6 +1,31 @@
 mod graph;
 mod config;
+mod search;
+mod view;
+mod tracking;
+mod archive;
+mod next;
+mod analysis;
+mod hierarchy;
+mod trash;
+mod restructure;
+mod templates;
+mod edit;
+mod diff;
+mod three_way;
 
 pub use graph::*;
 pub use config::*;
+pub use search::*;
+pub use view::*;
+pub use tracking::*;
+pub use archive::*;
+pub use next::*;
+pub use analysis::*;
+pub use hierarchy::*;
+pub use trash::*;
+pub use templates::*;
+pub use edit::*;
+pub use diff::*;
+pub use three_way::*;
 
@@ -1,11 +1,29 @@
-use std::fmt;
-use clap::{command, Parser, Subcommand};
+mod completion;
+mod tui;
+
+use std::{env, fmt, fs, process};
+use std::borrow::Cow;
+use std::io::{self, IsTerminal, Read, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
+use chrono::{Local, TimeZone};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
 use thiserror::Error;
 use tabled::{Table, Tabled};
-use tdc::{Config, ConfigError, Graph, GraphError, Task, TaskId, TaskOrder};
-use glob::{Pattern, PatternError};
+use tabled::builder::Builder;
+use tdc::{Archive, ArchivedTask, ArchiveId, Column, Config, DependencyInfo, DependencyKind, ConfigError, Graph, GraphError, GraphSource, Matcher, OrderPosition, TagInfo, Task, TaskId, TaskMatch, TaskOrder, TaskStatus, Template, Templates, TrashedTask};
+use tdc::{display_path, format_duration, parse_due, parse_duration, parse_edits, parse_since, render_tasks, NextFilter};
+use glob::PatternError;
+use completion::{Shell, COMPLETE_COMMAND};
 
 const INDENT: &str = "    ";
+const HIGHLIGHT_START: &str = "\x1b[1;33m";
+const HIGHLIGHT_END: &str = "\x1b[0m";
+/// Editor used by `tdc edit` when neither $VISUAL nor $EDITOR is set.
+const DEFAULT_EDITOR: &str = "vi";
+const DEFAULT_PATH_LIMIT: usize = 100;
+const TASK_IDS_HELP: &str = "Id of the task(s). Accepts ranges (3..9, 3..=9) and - to read ids from stdin";
 
 #[derive(Parser, Debug)]
 #[command(name="tdc")]
@@ -14,21 +32,33 @@ const INDENT: &str = "    ";
 struct Cli {
     #[command(subcommand)]
     command: Command,  
+    #[clap(long, global=true, help="Path of the config file. Overrides $TDC_CONFIG")]
+    config: Option<PathBuf>,
+    #[clap(long, global=true, help="Path of the graph file. Overrides $TDC_GRAPH and the config file")]
+    graph: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
 enum Command { 
+    #[command(name="init", about="Create a project graph in the current directory. Commands run in this directory or below it will use it.")]
+    Init,
+    #[command(name="where", about="Print the path of the graph in use and where it came from")]
+    Where,
     #[command(name="add", about="Add a task")]
     Add { 
         #[clap(help="Name of the task")]
         task_name: String,
     },
-    #[command(name="rm", about="Remove a task")]
+    #[command(name="rm", about="Remove a task, moving it to the trash")]
     Remove { 
         #[clap(short, long, help="Removes all tasks if set")]
         all: bool,
-        #[clap(help="Id of the task(s)")]
-        task_ids: Vec<TaskId>,
+        #[clap(help=TASK_IDS_HELP)]
+        task_ids: Vec<TaskIds>,
+        #[clap(short, long, conflicts_with="all", help="Also removes transitive dependencies that no other task needs")]
+        cascade: bool,
+        #[clap(long, requires="cascade", help="Lists the tasks --cascade would remove, without removing them")]
+        dry_run: bool,
     }, 
     #[command(name="rename", about="Rename a task")]
     Rename { 
@@ -37,83 +67,391 @@ enum Command {
         #[clap(help="New name of the task")]
         name: String,
     }, 
+    #[command(name="merge", about="Merge a task into another, which gains its tags, dependencies, dependents and logged time")]
+    Merge {
+        #[clap(help="Id of the task that survives the merge")]
+        task_id: TaskId,
+        #[clap(help="Id of the task merged into it, then removed")]
+        other_id: TaskId,
+    },
+    #[command(name="split", about="Replace a task with new tasks inheriting its tags, dependencies and dependents. Prints the ids of the new tasks.")]
+    Split {
+        #[clap(help="Id of the task")]
+        task_id: TaskId,
+        #[clap(required=true, help="Names of the new tasks")]
+        names: Vec<String>,
+    },
+    #[command(name="dup", about="Copy a task, keeping its dependencies. Prints the ids of the copies.")]
+    Duplicate {
+        #[clap(help="Id of the task")]
+        task_id: TaskId,
+        #[clap(short, long, help="Also copies transitive dependencies, linking the copies to each other")]
+        deep: bool,
+    },
+    #[command(
+        name="mv",
+        about="Move tasks under a parent task, grouping them without making them dependencies",
+        group(ArgGroup::new("destination").required(true).args(["parent", "root"])),
+    )]
+    Move {
+        #[clap(required=true, help=TASK_IDS_HELP)]
+        task_ids: Vec<TaskIds>,
+        #[clap(short, long, help="Id of the new parent task")]
+        parent: Option<TaskId>,
+        #[clap(long, help="Moves the task(s) back to the top level")]
+        root: bool,
+    },
     #[command(name="ls", about="List all tasks")]
-    List,
+    List {
+        #[clap(long, help="Prints task ids only, one per line")]
+        ids: bool,
+    },
     #[command(name="tree", about="Prints a tree view of one or more tasks and their dependencies")]
     Tree {
-        #[clap(help="Id of the task(s)")]
-        task_ids: Vec<TaskId>,
+        #[clap(help=TASK_IDS_HELP)]
+        task_ids: Vec<TaskIds>,
     },
-    #[command(name="find", about="Find tasks whose name contains the pattern provided")]
+    #[command(name="find", about="Find tasks whose name or tags fuzzy match the pattern provided, most relevant first")]
     Find {
         #[clap(help="Pattern to search for")]
         pattern: String,
-        #[clap(short, long, help="Treats pattern as a glob pattern if set")]
+        #[clap(short, long, conflicts_with="regex", help="Treats pattern as a glob pattern if set")]
         glob: bool,
+        #[clap(short, long, help="Treats pattern as a regular expression if set")]
+        regex: bool,
         #[clap(short, long, help="Matching will be case sensitive")]
         case_sensitive: bool,
+        #[clap(long, help="Prints task ids only, one per line")]
+        ids: bool,
     },
     #[command(name="sel", about="Select a task, adding it to the todo list")]
     Select { 
         #[clap(short, long, help="Selects all tasks if set")]
         all: bool,
-        #[clap(help="Id of the task(s)")]
-        task_ids: Vec<TaskId>,
+        #[clap(help=TASK_IDS_HELP)]
+        task_ids: Vec<TaskIds>,
     },
     #[command(name="desel", about="Deselects a task, removing it from the todo list")]
     Deselect { 
-        #[clap(help="Id of the task(s)")]
-        task_ids: Vec<TaskId>,
+        #[clap(help=TASK_IDS_HELP)]
+        task_ids: Vec<TaskIds>,
         #[clap(short, long, help="Deselects all tasks if set")]
         all: bool,
     },
-    #[command(name="todo", about="Show the todo list of tasks using currently selected tasks")]
+    #[command(name="status", about="Set the status of a task: todo, in-progress, blocked, done or cancelled")]
+    Status {
+        #[clap(required=true, help=TASK_IDS_HELP)]
+        task_ids: Vec<TaskIds>,
+        #[clap(help="New status of the task(s)")]
+        status: TaskStatus,
+    },
+    #[command(name="todo", about="Show the todo list of tasks using currently selected tasks. Done and cancelled tasks are left out.")]
     Todo {
-        #[clap(long, short, help="Shows all tasks on todo list, including those with dependencies")]
+        #[clap(long, short, help="Shows all unfinished tasks on todo list, including blocked ones")]
         all: bool,
     },
     #[command(name="depadd", about="Add dependencies to a task")]
     DepAdd {
         #[clap(help="Id of task receiving dependencies")]
         task_id: TaskId,
-        #[clap(required=true, help="Ids of tasks that will added as dependencies")]
-        dependency_ids: Vec<TaskId>,
+        #[clap(required=true, help="Ids of tasks that will added as dependencies. Accepts ranges (3..9) and - to read from stdin")]
+        dependency_ids: Vec<TaskIds>,
+        #[clap(short, long, default_value_t=DependencyKind::Hard, help="hard: blocks the task. soft: should be done first, without hiding the task from the todo list. related: doesn't block")]
+        kind: DependencyKind,
+        #[clap(short, long, help="Describes the dependency, shown by tdc tree")]
+        label: Option<String>,
     },
     #[command(name="deprm", about="Remove dependencies from a task")]
     DepRemove {
         #[clap(help="Id of task removing dependencies")]
         task_id: TaskId,
-        #[clap(required=true, help="Ids of tasks that will be removed as dependencies")]
-        dependency_ids: Vec<TaskId>,
+        #[clap(required=true, help="Ids of tasks that will be removed as dependencies. Accepts ranges (3..9) and - to read from stdin")]
+        dependency_ids: Vec<TaskIds>,
     },
     #[command(name="depclear", about="Clear dependencies of a task")]
     DepClear {
         #[clap(help="Id of task clearing dependencies")]
         task_id: TaskId,
     },
-    #[command(name="order", about="Set the order of a task using an integer. If not set, order is cleared.")]
-    Order {
-        #[clap(help="Id of task being ordered")]
+    #[command(name="deps", about="List dependencies across all tasks")]
+    Deps {
+        #[clap(long, help="Lists only dependencies already implied by other chains of dependencies")]
+        reduce: bool,
+        #[clap(long, requires="reduce", help="Removes the dependencies listed. Which tasks depend on which, directly or not, stays unchanged")]
+        apply: bool,
+    },
+    #[command(name="path", about="Print the chain of dependencies going from one task to another")]
+    Path {
+        #[clap(help="Id of the task to start from")]
         task_id: TaskId,
+        #[clap(help="Id of the task to reach through dependencies")]
+        target_id: TaskId,
+        #[clap(short, long, help="Prints every chain instead of the first one found")]
+        all: bool,
+        #[clap(short, long, requires="all", default_value_t=DEFAULT_PATH_LIMIT, help="Maximum number of chains printed with --all")]
+        limit: usize,
+    },
+    #[command(name="order", about="Set the order of a task using an integer, or move it relative to other tasks. If neither is set, order is cleared.")]
+    Order {
+        #[clap(required_unless_present="normalize", help="Id of task being ordered")]
+        task_id: Option<TaskId>,
+        #[clap(group="position")]
         order: Option<i32>,
+        #[clap(long, group="position", value_name="TASK_ID", help="Moves the task right before another task")]
+        before: Option<TaskId>,
+        #[clap(long, group="position", value_name="TASK_ID", help="Moves the task right after another task")]
+        after: Option<TaskId>,
+        #[clap(long, group="position", help="Moves the task before all ordered tasks")]
+        top: bool,
+        #[clap(long, group="position", help="Moves the task after all ordered tasks")]
+        bottom: bool,
+        #[clap(long, conflicts_with_all=["task_id", "position"], help="Renumbers all ordered tasks to 10, 20, 30... keeping them in the same order")]
+        normalize: bool,
     },
-    #[command(name="tags", about="Lists all tags across all tasks.")]
-    Tags,    
+    #[command(name="view", about="Show a named view defined in the config file. Lists views if no name is given.")]
+    View {
+        #[clap(help="Name of the view")]
+        name: Option<String>,
+        #[clap(long, help="Prints task ids only, one per line")]
+        ids: bool,
+    },
+    #[command(name="tags", about="Lists all tags across all tasks, with the number of tasks using each.")]
+    Tags,
+    #[command(name="tag", about="Manage tags across all tasks. Tags can be nested using slashes, ie: work/backend.")]
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },    
     #[command(name="tagadd", about="Add searchable tags to a task.")]
     TagAdd {
-        #[clap(help="Task to add tags to")]
-        task_id: TaskId,
+        #[clap(help="Task(s) to add tags to. Accepts a range (3..9) or - to read from stdin")]
+        task_ids: TaskIds,
         tags: Vec<String>,
     },
     #[command(name="tagrm", about="Removes tags from a task.")]
     TagRemove {
-        #[clap(help="Task to add a tag to")]
-        task_id: TaskId,
+        #[clap(help="Task(s) to remove tags from. Accepts a range (3..9) or - to read from stdin")]
+        task_ids: TaskIds,
         tags: Vec<String>,
     },
     #[command(name="tagfind", about="Finds a task that has all of the tags specified.")]
     TagFind {
         tags: Vec<String>,
+        #[clap(long, help="Prints task ids only, one per line")]
+        ids: bool,
+    },
+    #[command(name="diff", about="Show the differences between two graph files: tasks, names, statuses, tags, orders and dependencies")]
+    Diff {
+        #[clap(help="Older graph file")]
+        old_path: PathBuf,
+        #[clap(help="Newer graph file. Defaults to the graph in use")]
+        new_path: Option<PathBuf>,
+    },
+    #[command(name="merge-driver", about="Merge two versions of a graph file with their common ancestor, task by task. Meant to be registered as a git merge driver, see the README.")]
+    MergeDriver {
+        #[clap(help="Common ancestor, %O in git")]
+        base_path: PathBuf,
+        #[clap(help="Our version, %A in git. Overwritten with the merge")]
+        ours_path: PathBuf,
+        #[clap(help="Their version, %B in git")]
+        theirs_path: PathBuf,
+    },
+    #[command(name="edit", about="Edit tasks as text in $VISUAL or $EDITOR: names, tags, order and dependencies. Removed lines move tasks to the trash.")]
+    Edit {
+        #[clap(short, long, conflicts_with="tag", help="Only edits tasks on the todo list")]
+        selected: bool,
+        #[clap(short, long, help="Only edits tasks having all of these tags")]
+        tag: Vec<String>,
+    },
+    #[command(name="completions", about="Prints a shell completion script. Source it from your shell's startup file.")]
+    Completions {
+        #[clap(help="Shell to generate the script for")]
+        shell: Shell,
+    },
+    #[command(name="start", about="Start tracking time on a task, stopping the running timer if any")]
+    Start {
+        #[clap(help="Id of the task")]
+        task_id: TaskId,
+    },
+    #[command(name="stop", about="Stop the running timer, logging the time spent on its task")]
+    Stop,
+    #[command(name="due", about="Set the due date of a task. If not set, the due date is cleared.")]
+    Due {
+        #[clap(required=true, help=TASK_IDS_HELP)]
+        task_ids: Vec<TaskIds>,
+        #[clap(long, short, help="Due date: today, tomorrow, a weekday (friday), a date (2025-01-31) or a duration from now (3d)")]
+        date: Option<String>,
+    },
+    #[command(name="estimate", about="Set the estimated effort of a task, ie: 30m, 2h. If not set, the estimate is cleared.")]
+    Estimate {
+        #[clap(required=true, help=TASK_IDS_HELP)]
+        task_ids: Vec<TaskIds>,
+        #[clap(long, short, help="Estimated effort, ie: 45m, 1h30m, 2h. A bare number is treated as minutes")]
+        effort: Option<String>,
+    },
+    #[command(name="next", about="Recommend the doable task of the todo list to work on next, explaining its score")]
+    Next {
+        #[clap(short, long, help="Only considers tasks with this tag. Can be repeated")]
+        tag: Vec<String>,
+        #[clap(long, help="Only considers tasks estimated to fit in this time, ie: 30m")]
+        time: Option<String>,
+        #[clap(short, long, default_value_t=1, help="Number of tasks to recommend")]
+        count: usize,
+    },
+    #[command(name="orphans", about="List tasks that are not reachable from any selected task, ie: left out of the todo list")]
+    Orphans {
+        #[clap(long, help="Prints task ids only, one per line")]
+        ids: bool,
+    },
+    #[command(name="unblocks", about="List doable tasks by how much downstream work finishing them would unblock, most first")]
+    Unblocks {
+        #[clap(short, long, help="Only considers doable tasks of the todo list")]
+        selected: bool,
+    },
+    #[command(name="log", about="Log time spent on a task, ie: 45m, 1h30m")]
+    Log {
+        #[clap(help="Id of the task")]
+        task_id: TaskId,
+        #[clap(help="Time spent, ie: 45m, 1h30m, 2h. A bare number is treated as minutes")]
+        duration: String,
+    },
+    #[command(name="report", about="Report time spent per task or per tag. Task totals include time spent on their transitive dependencies.")]
+    Report {
+        #[clap(short, long, help="Start of the period: today, yesterday, a weekday (monday), a date (2025-01-31) or a duration (7d)")]
+        since: Option<String>,
+        #[clap(short, long, value_enum, default_value_t=ReportBy::Task, help="What to group time by")]
+        by: ReportBy,
+    },
+    #[command(name="archive", about="Move finished tasks whose dependents are all finished into the archive file. Prints the ids of archived tasks.")]
+    #[command(args_conflicts_with_subcommands=true)]
+    Archive {
+        #[clap(long, help="Only archives tasks finished at least this long ago, ie: 30d")]
+        older_than: Option<String>,
+        #[command(subcommand)]
+        command: Option<ArchiveCommand>,
+    },
+    #[command(name="trash", about="List or empty the trash")]
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
+    },
+    #[command(name="restore", about="Move tasks out of the trash, restoring their dependencies, dependents and parent")]
+    Restore {
+        #[clap(required=true, help="Id of the trashed task(s)")]
+        trashed_ids: Vec<TaskId>,
+    },
+    #[command(name="template", about="Save groups of tasks as reusable templates, and create tasks from them")]
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommand,
+    },
+    #[command(name="tui", about="Opens a full-screen terminal UI")]
+    Tui,
+    #[command(name=COMPLETE_COMMAND, hide=true)]
+    Complete {
+        #[clap(trailing_var_arg=true, allow_hyphen_values=true, help="Words following tdc, ending with the word being completed")]
+        words: Vec<String>,
+    },
+}
+
+#[derive(ValueEnum, Copy, Clone, Eq, PartialEq, Debug)]
+enum ReportBy {
+    Task,
+    Tag,
+}
+
+#[derive(Subcommand, Debug)]
+enum ArchiveCommand {
+    #[command(name="ls", about="List archived tasks")]
+    List {
+        #[clap(long, help="Prints archive ids only, one per line")]
+        ids: bool,
+    },
+    #[command(name="search", about="Find archived tasks whose name or tags fuzzy match the pattern provided, most relevant first")]
+    Search {
+        #[clap(help="Pattern to search for")]
+        pattern: String,
+        #[clap(short, long, conflicts_with="regex", help="Treats pattern as a glob pattern if set")]
+        glob: bool,
+        #[clap(short, long, help="Treats pattern as a regular expression if set")]
+        regex: bool,
+        #[clap(short, long, help="Matching will be case sensitive")]
+        case_sensitive: bool,
+        #[clap(long, help="Prints archive ids only, one per line")]
+        ids: bool,
+    },
+    #[command(name="restore", about="Move archived tasks back into the graph, without their dependencies. Prints the new ids of the tasks.")]
+    Restore {
+        #[clap(required=true, help="Archive id of the task(s)")]
+        archive_ids: Vec<ArchiveId>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TemplateCommand {
+    #[command(name="save", about="Save tasks and their transitive dependencies as a template, replacing any template with the same name")]
+    Save {
+        #[clap(help="Name of the template")]
+        name: String,
+        #[clap(required=true, help=TASK_IDS_HELP)]
+        task_ids: Vec<TaskIds>,
+        #[clap(short, long="var", value_name="NAME=VALUE", value_parser=parse_variable, help="Replaces a value in task names with a placeholder, ie: version=1.3 turns 'Release 1.3' into 'Release {version}'")]
+        variables: Vec<(String, String)>,
+    },
+    #[command(name="ls", about="List templates")]
+    List,
+    #[command(name="apply", about="Create tasks from a template. Prints the ids of the new tasks.")]
+    Apply {
+        #[clap(help="Name of the template")]
+        name: String,
+        #[clap(value_name="NAME=VALUE", value_parser=parse_variable, help="Values of the placeholders in task names, ie: version=1.4")]
+        variables: Vec<(String, String)>,
+    },
+    #[command(name="rm", about="Remove a template")]
+    Remove {
+        #[clap(help="Name of the template")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TrashCommand {
+    #[command(name="ls", about="List trashed tasks")]
+    List {
+        #[clap(long, help="Prints task ids only, one per line")]
+        ids: bool,
+    },
+    #[command(name="empty", about="Permanently delete trashed tasks, freeing their ids. Prints the number of tasks deleted.")]
+    Empty,
+}
+
+#[derive(Subcommand, Debug)]
+enum TagCommand {
+    #[command(name="rename", about="Rename a tag and its sub-tags on every task. Prints the number of tasks modified.")]
+    Rename {
+        #[clap(help="Tag to rename")]
+        tag: String,
+        #[clap(help="New name of the tag. Must not already be in use")]
+        new_tag: String,
+    },
+    #[command(name="merge", about="Merge a tag and its sub-tags into another on every task. Prints the number of tasks modified.")]
+    Merge {
+        #[clap(help="Tag to merge away")]
+        tag: String,
+        #[clap(help="Tag to merge into")]
+        target_tag: String,
+    },
+    #[command(name="desc", about="Set the description of a tag. If not set, description is cleared.")]
+    Describe {
+        #[clap(help="Tag to describe")]
+        tag: String,
+        description: Option<String>,
+    },
+    #[command(name="color", about="Set the display color of a tag as a hex string, ie: #ff8800. If not set, color is cleared.")]
+    Color {
+        #[clap(help="Tag to color")]
+        tag: String,
+        color: Option<String>,
     },
 }
 
@@ -126,28 +464,86 @@ fn main() {
 
 fn run() -> Result<()> {
     let cli = Cli::parse();
-    run_command(cli.command)?;
+    match cli.command {
+        Command::Completions { shell } => print!("{}", shell.script()),
+        Command::Complete { words } => completion::print_candidates(&words),
+        Command::MergeDriver { base_path, ours_path, theirs_path } => {
+            let base = Graph::load_merge_base(&base_path)?;
+            let ours = Graph::load_file(&ours_path)?;
+            let theirs = Graph::load_file(&theirs_path)?;
+            let (merged, conflicts) = Graph::merge_three_way(&base, &ours, &theirs);
+            merged.save_file(&ours_path)?;
+            for conflict in &conflicts {
+                eprintln!("Conflict: {conflict}");
+            }
+            if !conflicts.is_empty() {
+                return Err(AppError::MergeConflicts(conflicts.len()));
+            }
+        },
+        command => {
+            let current_dir = env::current_dir()?;
+            let config = Config::load_with(cli.config.as_deref(), cli.graph.as_deref(), &current_dir)?;
+            run_command(command, config)?;
+        },
+    }
     Ok(())
 }
 
 /// Runs a command on a graph.
-fn run_command(command: Command) -> Result<()> {
-    let config = Config::load()?;
+fn run_command(command: Command, config: Config) -> Result<()> {
     match command {
+        Command::Init => {
+            let graph_path = tdc::project_graph_path(&env::current_dir()?);
+            if graph_path.exists() {
+                return Err(AppError::ProjectExists(graph_path));
+            }
+            let config = Config { graph_path, ..config };
+            Graph::new().save(&config)?;
+            println!("{}", config.graph_path.display());
+        },
+        Command::Where => {
+            let source = match config.graph_source {
+                GraphSource::Config => "config file",
+                GraphSource::Environment => tdc::GRAPH_ENV_VAR,
+                GraphSource::Project => "project",
+                GraphSource::CommandLine => "--graph flag",
+            };
+            println!("{} ({source})", config.graph_path.display());
+        },
         Command::Add { task_name } => {
             let mut graph = Graph::load(&config)?;
-            let task_id = graph.insert(Task::new(task_name));
+            let mut task = Task::new(task_name);
+            task.created_at = Some(Local::now().timestamp());
+            let task_id = graph.insert(task);
             graph.save(&config)?;
             println!("{task_id}");
         },
-        Command::Remove { task_ids, all } => {
+        Command::Remove { task_ids, all, cascade, dry_run } => {
             let mut graph = Graph::load(&config)?;
-            if all {
-                graph.clear();
+            let now = Local::now().timestamp();
+            if cascade && !task_ids.is_empty() {
+                let task_ids = graph.cascade(&resolve_task_ids(&task_ids, &graph)?)?;
+                if dry_run {
+                    let task_rows = task_ids
+                        .iter()
+                        .map(|task_id| TaskRow::new(&graph, *task_id, graph.get(*task_id).unwrap()))
+                        .collect();
+                    print_task_rows(task_rows, false);
+                    return Ok(());
+                }
+                for task_id in task_ids {
+                    graph.trash(task_id, now)?;
+                }
             }
-            else if !task_ids.is_empty() {
+            else if all {
+                let task_ids: Vec<TaskId> = graph.iter().map(|(task_id, _)| task_id).collect();
                 for task_id in task_ids {
-                    graph.remove(task_id).ok_or(GraphError::TaskNotFound)?;
+                    graph.trash(task_id, now)?;
+                }
+            }
+            else if !task_ids.is_empty() {
+                for task_id in resolve_task_ids(&task_ids, &graph)? {
+                    graph.trash(task_id, now)?;
                 }
             }
             else {
@@ -161,13 +557,42 @@ fn run_command(command: Command) -> Result<()> {
             task.name = name;
             graph.save(&config)?;
         },
+        Command::Merge { task_id, other_id } => {
+            let mut graph = Graph::load(&config)?;
+            graph.merge(task_id, other_id)?;
+            graph.save(&config)?;
+        },
+        Command::Split { task_id, names } => {
+            let mut graph = Graph::load(&config)?;
+            let part_ids = graph.split(task_id, &names)?;
+            graph.save(&config)?;
+            for part_id in part_ids {
+                println!("{part_id}");
+            }
+        },
+        Command::Duplicate { task_id, deep } => {
+            let mut graph = Graph::load(&config)?;
+            let copies = graph.duplicate(task_id, deep, Local::now().timestamp())?;
+            graph.save(&config)?;
+            for (_, copy_id) in copies {
+                println!("{copy_id}");
+            }
+        },
+        // With --root, the argument group leaves parent unset
+        Command::Move { task_ids, parent, root: _ } => {
+            let mut graph = Graph::load(&config)?;
+            for task_id in resolve_task_ids(&task_ids, &graph)? {
+                graph.set_parent(task_id, parent)?;
+            }
+            graph.save(&config)?;
+        },
         Command::Select { task_ids, all } => {
             let mut graph = Graph::load(&config)?;
             if all {
                 graph.set_selected_all(true);
             }
             else if !task_ids.is_empty() {
-                for task_id in task_ids {
+                for task_id in resolve_task_ids(&task_ids, &graph)? {
                     graph.set_selected(task_id, true)?;
                 }
             }
@@ -182,7 +607,7 @@ fn run_command(command: Command) -> Result<()> {
                 graph.set_selected_all(false);
             }
             else if !task_ids.is_empty() {
-                for task_id in task_ids {
+                for task_id in resolve_task_ids(&task_ids, &graph)? {
                     graph.set_selected(task_id, false)?;
                 }
             }
@@ -191,80 +616,60 @@ fn run_command(command: Command) -> Result<()> {
             }
             graph.save(&config)?;
         },
+        Command::Status { task_ids, status } => {
+            let mut graph = Graph::load(&config)?;
+            for task_id in resolve_task_ids(&task_ids, &graph)? {
+                graph.set_status(task_id, status, Local::now().timestamp())?;
+            }
+            graph.save(&config)?;
+        },
         Command::Todo { all } => {
             let graph = Graph::load(&config)?;
-            let tasks = graph.traverse_selected();
-            let mut task_rows: Vec<TaskRow> = tasks
-                .into_iter()
-                .map(|(task_id, task)| TaskRow::new(task_id, task))
-                .filter(|task| all || task.doable())
-                .collect();
-            task_rows.sort_by_key(|task_row| (!task_row.doable(), task_row.order));
+            let task_rows = todo_rows(&graph, all);
             let task_table = Table::new(task_rows);
             println!("{task_table}");
         },
-        Command::List => {
+        Command::List { ids } => {
             let graph = Graph::load(&config)?;
-            let mut task_rows: Vec<TaskRow> = graph.iter() 
-                .map(|(task_id, task)| TaskRow::new(task_id, task))
+            let task_rows: Vec<TaskRow> = graph
+                .hierarchy(|_, task| !task.selected)
+                .into_iter()
+                .map(|(task_id, depth)| TaskRow::new(&graph, task_id, graph.get(task_id).unwrap()).nested(&graph, depth))
                 .collect();
-            task_rows.sort_by_key(|task_row| !task_row.selected);
-            let task_table = Table::new(task_rows);
-            println!("{task_table}");
+            print_task_rows(task_rows, ids);
         },
         Command::Tree { task_ids } => {
             let graph = Graph::load(&config)?;
-            for task_id in task_ids {
+            for task_id in resolve_task_ids(&task_ids, &graph)? {
                 let task = graph.get(task_id).ok_or(GraphError::TaskNotFound)?;
                 let task_tree = TaskTree { task_id, task, graph: &graph };
                 print!("{task_tree}");
             }
         },
-        Command::Find { pattern, glob, case_sensitive } => {
+        Command::Find { pattern, glob, regex, case_sensitive, ids } => {
             let graph = Graph::load(&config)?;
-            let mut task_rows: Vec<TaskRow> = match (glob, case_sensitive) {
-                (false, false) => {
-                    let pattern = pattern.to_uppercase();
-                    graph.iter() 
-                        .filter(|(_, task)| task.name.to_uppercase().contains(&pattern))
-                        .map(|(task_id, task)| TaskRow::new(task_id, task))
-                        .collect()
-                },
-                (false, true) => {
-                    graph.iter() 
-                        .filter(|(_, task)| task.name.contains(&pattern))
-                        .map(|(task_id, task)| TaskRow::new(task_id, task))
-                        .collect()
-                },
-                (true, false) => {
-                    let pattern = Pattern::new(&pattern.to_uppercase())?;
-                    graph.iter() 
-                        .filter(|(_, task)| pattern.matches(&task.name.to_uppercase()))
-                        .map(|(task_id, task)| TaskRow::new(task_id, task))
-                        .collect()
-                },
-                (true, true) => {
-                    let pattern = Pattern::new(&pattern)?;
-                    graph.iter() 
-                        .filter(|(_, task)| pattern.matches(&task.name))
-                        .map(|(task_id, task)| TaskRow::new(task_id, task))
-                        .collect()
-                },
-            };
-            task_rows.sort_by_key(|task_row| !task_row.selected);
-            let task_table = Table::new(task_rows);
-            println!("{task_table}");
+            let matcher = matcher(pattern, glob, regex, case_sensitive)?;
+            let highlight = io::stdout().is_terminal();
+            let task_rows: Vec<TaskRow> = graph.search(&matcher)
+                .into_iter()
+                .map(|(task_id, task, task_match)| {
+                    let task_row = TaskRow::new(&graph, task_id, task);
+                    if highlight { task_row.highlighted(&task_match) } else { task_row }
+                })
+                .collect();
+            print_task_rows(task_rows, ids);
         },
-        Command::DepAdd { task_id, dependency_ids } => {
+        Command::DepAdd { task_id, dependency_ids, kind, label } => {
             let mut graph = Graph::load(&config)?;
-            for dependency_id in dependency_ids {
-                graph.insert_dependency(task_id, dependency_id)?;
+            let info = DependencyInfo { kind, label };
+            for dependency_id in resolve_task_ids(&dependency_ids, &graph)? {
+                graph.insert_dependency_with(task_id, dependency_id, info.clone())?;
             }
             graph.save(&config)?;
         },
         Command::DepRemove { task_id, dependency_ids } => {
             let mut graph = Graph::load(&config)?;
-            for dependency_id in dependency_ids {
+            for dependency_id in resolve_task_ids(&dependency_ids, &graph)? {
                 graph.remove_dependency(task_id, dependency_id)?;
             }
             graph.save(&config)?;
@@ -274,60 +679,607 @@ fn run_command(command: Command) -> Result<()> {
             graph.clear_dependencies(task_id)?;
             graph.save(&config)?;
         },
-        Command::Order { task_id, order } => {
+        Command::Deps { reduce: false, .. } => {
+            let graph = Graph::load(&config)?;
+            let dependency_rows: Vec<DependencyRow> = graph
+                .iter()
+                .flat_map(|(task_id, task)| task.dependencies().iter().map(move |dependency_id| DependencyRow {
+                    task: task_id,
+                    dependency: *dependency_id,
+                    kind: task.dependency_kind(*dependency_id),
+                    label: task.dependency_info(*dependency_id).and_then(|info| info.label.as_deref()),
+                }))
+                .collect();
+            println!("{}", Table::new(dependency_rows));
+        },
+        Command::Deps { reduce: true, apply } => {
             let mut graph = Graph::load(&config)?;
-            let task = graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
-            let order = match order { 
-                Some(order) => TaskOrder::Order(order),
-                None => TaskOrder::Last,
+            let redundant = graph.redundant_dependencies();
+            let redundant_rows: Vec<RedundantRow> = redundant
+                .iter()
+                .map(|dependency| RedundantRow {
+                    task: dependency.task_id,
+                    dependency: dependency.dependency_id,
+                    implied_by: display_path(&dependency.path),
+                })
+                .collect();
+            println!("{}", Table::new(redundant_rows));
+            if apply && !redundant.is_empty() {
+                for dependency in redundant {
+                    graph.remove_dependency(dependency.task_id, dependency.dependency_id)?;
+                }
+                graph.save(&config)?;
+            }
+        },
+        Command::Path { task_id, target_id, all, limit } => {
+            let graph = Graph::load(&config)?;
+            let mut paths = match all {
+                true => graph.find_all_paths(task_id, target_id, limit.saturating_add(1))?,
+                false => graph.find_path(task_id, target_id)?.into_iter().collect(),
+            };
+            if paths.is_empty() {
+                return Err(AppError::NoPath(task_id, target_id));
+            }
+            if paths.len() > limit {
+                paths.truncate(limit);
+                eprintln!("Only the first {limit} chains are printed. Raise --limit to see more");
+            }
+            for (i, path) in paths.iter().enumerate() {
+                if i > 0 { println!() }
+                for (depth, path_task_id) in path.iter().enumerate() {
+                    let task = graph.get(*path_task_id).ok_or(GraphError::TaskNotFound)?;
+                    println!("{}{}) {}", INDENT.repeat(depth), path_task_id, task.name);
+                }
+            }
+        },
+        Command::Order { task_id, order, before, after, top, bottom, normalize } => {
+            let mut graph = Graph::load(&config)?;
+            let Some(task_id) = task_id else {
+                debug_assert!(normalize);
+                graph.normalize_orders();
+                graph.save(&config)?;
+                return Ok(());
+            };
+            let position = match (before, after) {
+                (Some(other_id), _) => Some(OrderPosition::Before(other_id)),
+                (_, Some(other_id)) => Some(OrderPosition::After(other_id)),
+                _ if top => Some(OrderPosition::Top),
+                _ if bottom => Some(OrderPosition::Bottom),
+                _ => None,
             };
-            task.order = order;
+            match position {
+                Some(position) => graph.reorder(task_id, position)?,
+                None => {
+                    let task = graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
+                    task.order = match order { 
+                        Some(order) => TaskOrder::Order(order),
+                        None => TaskOrder::Last,
+                    };
+                },
+            }
             graph.save(&config)?;
         },
+        Command::View { name, ids } => {
+            let Some(name) = name else {
+                for name in config.views.keys() {
+                    println!("{name}");
+                }
+                return Ok(());
+            };
+            let view = config.views.get(&name).ok_or(AppError::ViewNotFound(name))?;
+            let graph = Graph::load(&config)?;
+            let task_rows: Vec<TaskRow> = view.apply(&graph)
+                .into_iter()
+                .map(|(task_id, task)| TaskRow::new(&graph, task_id, task))
+                .collect();
+            if ids || view.columns.is_empty() {
+                print_task_rows(task_rows, ids);
+            }
+            else {
+                print_task_columns(task_rows, &view.columns);
+            }
+        },
         Command::Tags => {
             let graph = Graph::load(&config)?;
-            for task in graph.tags() {
-                println!("{task}");
+            let tag_rows: Vec<TagRow> = graph.tag_counts()
+                .into_iter()
+                .map(|(tag, count)| TagRow::new(tag, count, graph.tag_info(tag)))
+                .collect();
+            let tag_table = Table::new(tag_rows);
+            println!("{tag_table}");
+        },
+        Command::Tag { command } => {
+            let mut graph = Graph::load(&config)?;
+            match command {
+                TagCommand::Rename { tag, new_tag } => {
+                    let modified = graph.rename_tag(&tag, &new_tag)?;
+                    println!("{modified}");
+                },
+                TagCommand::Merge { tag, target_tag } => {
+                    let modified = graph.merge_tag(&tag, &target_tag)?;
+                    println!("{modified}");
+                },
+                TagCommand::Describe { tag, description } => {
+                    graph.tag_info_mut(&tag).description = description;
+                },
+                TagCommand::Color { tag, color } => {
+                    if let Some(color) = &color {
+                        if !is_hex_color(color) { return Err(AppError::InvalidColor(color.clone())) }
+                    }
+                    graph.tag_info_mut(&tag).color = color;
+                },
             }
+            graph.save(&config)?;
         },
-        Command::TagAdd { task_id, tags } => {
+        Command::TagAdd { task_ids, tags } => {
             let mut graph = Graph::load(&config)?;
             let mut modified = false;
-            let task = graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
-            for tag in tags {
-                if task.add_tag(tag) {
-                    modified = true;
+            for task_id in resolve_task_ids(&[task_ids], &graph)? {
+                let task = graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
+                for tag in &tags {
+                    if task.add_tag(tag.clone()) {
+                        modified = true;
+                    }
                 }
             }
             if modified {
                 graph.save(&config)?;
             }
         },
-        Command::TagRemove { task_id, tags } => {
+        Command::TagRemove { task_ids, tags } => {
             let mut graph = Graph::load(&config)?;
             let mut modified = false;
-            let task = graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
-            for tag in tags {
-                if task.remove_tag(&tag) {
-                    modified = true;
+            for task_id in resolve_task_ids(&[task_ids], &graph)? {
+                let task = graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
+                for tag in &tags {
+                    if task.remove_tag(tag) {
+                        modified = true;
+                    }
                 }
             }
             if modified {
                 graph.save(&config)?;
             }
         },
-        Command::TagFind { tags } => {
+        Command::TagFind { tags, ids } => {
             let graph = Graph::load(&config)?;
             let task_rows: Vec<TaskRow> = graph.iter_with_tags(&tags)
-                .map(|(task_id, task)| TaskRow::new(task_id, task))
+                .map(|(task_id, task)| TaskRow::new(&graph, task_id, task))
                 .collect();
-            let task_table = Table::new(task_rows);
-            println!("{task_table}");
+            print_task_rows(task_rows, ids);
+        },
+        Command::Diff { old_path, new_path } => {
+            let old = Graph::load_file(&old_path)?;
+            let new = match new_path {
+                Some(new_path) => Graph::load_file(&new_path)?,
+                None => Graph::load(&config)?,
+            };
+            for change in new.diff(&old) {
+                println!("{change}");
+            }
+        },
+        Command::Edit { selected, tag } => {
+            let mut graph = Graph::load(&config)?;
+            let task_ids: Vec<TaskId> = match selected {
+                true => graph.traverse_selected().into_iter().map(|(task_id, _)| task_id).collect(),
+                false => graph.iter_with_tags(&tag).map(|(task_id, _)| task_id).collect(),
+            };
+            let text = render_tasks(&graph, &task_ids);
+            let path = create_edit_file(&text)?;
+            let editor = env::var("VISUAL")
+                .or_else(|_| env::var("EDITOR"))
+                .unwrap_or_else(|_| DEFAULT_EDITOR.to_owned());
+            // Runs through the shell, so that editors given with arguments work, ie: "code --wait"
+            let status = process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("{editor} \"$1\""))
+                .arg("sh")
+                .arg(&path)
+                .status()?;
+            if !status.success() {
+                fs::remove_file(&path)?;
+                return Err(AppError::EditorFailed(editor));
+            }
+            let edited_text = fs::read_to_string(&path)?;
+            if edited_text == text {
+                fs::remove_file(&path)?;
+                return Ok(());
+            }
+            let summary = parse_edits(&edited_text)
+                .and_then(|edits| graph.apply_edits(&task_ids, &edits, Local::now().timestamp()))
+                .map_err(|error| AppError::EditRejected(error, path.clone()))?;
+            graph.save(&config)?;
+            fs::remove_file(&path)?;
+            println!("added: {}, changed: {}, removed: {}", summary.added.len(), summary.changed.len(), summary.removed.len());
+        },
+        Command::Start { task_id } => {
+            let mut graph = Graph::load(&config)?;
+            let now = Local::now().timestamp();
+            if let Some((stopped_id, entry)) = graph.start_timer(task_id, now)? {
+                let stopped = graph.get(stopped_id).ok_or(GraphError::TaskNotFound)?;
+                println!("Stopped {stopped_id}) {} after {}", stopped.name, format_duration(entry.seconds));
+            }
+            graph.save(&config)?;
+        },
+        Command::Stop => {
+            let mut graph = Graph::load(&config)?;
+            let (task_id, entry) = graph.stop_timer(Local::now().timestamp()).ok_or(AppError::NoTimerRunning)?;
+            let task = graph.get(task_id).ok_or(GraphError::TaskNotFound)?;
+            println!("Logged {} on {task_id}) {}", format_duration(entry.seconds), task.name);
+            graph.save(&config)?;
+        },
+        Command::Due { task_ids, date } => {
+            let mut graph = Graph::load(&config)?;
+            let due = match date {
+                Some(date) => Some(parse_due(&date, Local::now()).ok_or(AppError::InvalidDate(date))?.timestamp()),
+                None => None,
+            };
+            for task_id in resolve_task_ids(&task_ids, &graph)? {
+                graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?.due = due;
+            }
+            graph.save(&config)?;
         },
+        Command::Estimate { task_ids, effort } => {
+            let mut graph = Graph::load(&config)?;
+            let estimate = match effort {
+                Some(effort) => Some(parse_duration(&effort).ok_or(AppError::InvalidDuration(effort))?),
+                None => None,
+            };
+            for task_id in resolve_task_ids(&task_ids, &graph)? {
+                graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?.estimate = estimate;
+            }
+            graph.save(&config)?;
+        },
+        Command::Next { tag, time, count } => {
+            let graph = Graph::load(&config)?;
+            let time = match time {
+                Some(time) => Some(parse_duration(&time).ok_or(AppError::InvalidDuration(time))?),
+                None => None,
+            };
+            let filter = NextFilter { tags: tag, time };
+            let recommendations = graph.recommend(&config.scoring, &filter, Local::now().timestamp());
+            if recommendations.is_empty() {
+                return Err(AppError::NothingToDo);
+            }
+            for (i, recommendation) in recommendations.into_iter().take(count).enumerate() {
+                if i > 0 { println!() }
+                println!("{}) {}", recommendation.task_id, recommendation.task.name);
+                println!("{INDENT}score: {:.2}", recommendation.score);
+                for reason in recommendation.reasons {
+                    println!("{INDENT}{:+.2} {}: {}", reason.points, reason.factor, reason.explanation);
+                }
+            }
+        },
+        Command::Orphans { ids } => {
+            let graph = Graph::load(&config)?;
+            let task_rows = graph
+                .orphans()
+                .into_iter()
+                .map(|task_id| TaskRow::new(&graph, task_id, graph.get(task_id).unwrap()))
+                .collect();
+            print_task_rows(task_rows, ids);
+        },
+        Command::Unblocks { selected } => {
+            let graph = Graph::load(&config)?;
+            let unblocks_rows: Vec<UnblocksRow> = graph.bottlenecks(selected)
+                .into_iter()
+                .filter(|bottleneck| !bottleneck.unblocked.is_empty())
+                .map(|bottleneck| UnblocksRow {
+                    id: bottleneck.task_id,
+                    name: &graph.get(bottleneck.task_id).unwrap().name,
+                    direct: bottleneck.direct,
+                    total: bottleneck.unblocked.len(),
+                    effort: bottleneck.effort,
+                })
+                .collect();
+            println!("{}", Table::new(unblocks_rows));
+        },
+        Command::Log { task_id, duration } => {
+            let mut graph = Graph::load(&config)?;
+            let seconds = parse_duration(&duration).ok_or(AppError::InvalidDuration(duration))?;
+            graph.log_time(task_id, seconds, Local::now().timestamp())?;
+            graph.save(&config)?;
+        },
+        Command::Report { since, by } => {
+            let graph = Graph::load(&config)?;
+            let since = match since {
+                Some(since) => parse_since(&since, Local::now()).ok_or(AppError::InvalidSince(since))?.timestamp(),
+                None => i64::MIN,
+            };
+            match by {
+                ReportBy::Task => {
+                    let mut time_rows = vec![];
+                    for (task_id, task) in graph.iter() {
+                        let time = graph.time_spent(task_id, since)?;
+                        let total = graph.rolled_up_time(task_id, since)?;
+                        if total == 0 { continue }
+                        time_rows.push(TaskTimeRow { id: task_id, name: &task.name, time, total });
+                    }
+                    time_rows.sort_by_key(|time_row| (std::cmp::Reverse(time_row.total), time_row.id));
+                    println!("{}", Table::new(time_rows));
+                },
+                ReportBy::Tag => {
+                    let tag_rows: Vec<TagTimeRow> = graph.time_by_tag(since)?
+                        .into_iter()
+                        .map(|(tag, time)| TagTimeRow { tag, time })
+                        .collect();
+                    println!("{}", Table::new(tag_rows));
+                },
+            }
+        },
+        Command::Archive { older_than, command: None } => {
+            let mut graph = Graph::load(&config)?;
+            let mut archive = Archive::load(&config)?;
+            let min_age = match older_than {
+                Some(older_than) => Some(parse_duration(&older_than).ok_or(AppError::InvalidDuration(older_than))?),
+                None => None,
+            };
+            let archived = graph.archive(&mut archive, min_age, Local::now().timestamp());
+            if archived.is_empty() { return Ok(()) }
+            // Archive is saved first so tasks are never lost
+            archive.save(&config)?;
+            graph.save(&config)?;
+            for (task_id, _) in archived {
+                println!("{task_id}");
+            }
+        },
+        Command::Archive { command: Some(command), .. } => {
+            let mut archive = Archive::load(&config)?;
+            match command {
+                ArchiveCommand::List { ids } => {
+                    let archive_rows = archive.iter().map(|(archive_id, entry)| ArchiveRow::new(archive_id, entry)).collect();
+                    print_archive_rows(archive_rows, ids);
+                },
+                ArchiveCommand::Search { pattern, glob, regex, case_sensitive, ids } => {
+                    let matcher = matcher(pattern, glob, regex, case_sensitive)?;
+                    let archive_rows = archive.search(&matcher)
+                        .into_iter()
+                        .map(|(archive_id, entry, _)| ArchiveRow::new(archive_id, entry))
+                        .collect();
+                    print_archive_rows(archive_rows, ids);
+                },
+                ArchiveCommand::Restore { archive_ids } => {
+                    let mut graph = Graph::load(&config)?;
+                    let mut task_ids = vec![];
+                    for archive_id in archive_ids {
+                        task_ids.push(archive.restore(archive_id, &mut graph)?);
+                    }
+                    // Graph is saved first so tasks are never lost
+                    graph.save(&config)?;
+                    archive.save(&config)?;
+                    for task_id in task_ids {
+                        println!("{task_id}");
+                    }
+                },
+            }
+        },
+        Command::Trash { command } => {
+            let mut graph = Graph::load(&config)?;
+            match command {
+                TrashCommand::List { ids } => {
+                    if ids {
+                        for trashed in graph.trashed() {
+                            println!("{}", trashed.task_id);
+                        }
+                    }
+                    else {
+                        let trash_rows: Vec<TrashRow> = graph.trashed().iter().map(TrashRow::new).collect();
+                        println!("{}", Table::new(trash_rows));
+                    }
+                },
+                TrashCommand::Empty => {
+                    let count = graph.empty_trash();
+                    graph.save(&config)?;
+                    println!("{count}");
+                },
+            }
+        },
+        Command::Restore { trashed_ids } => {
+            let mut graph = Graph::load(&config)?;
+            for task_id in trashed_ids {
+                graph.restore(task_id)?;
+            }
+            graph.save(&config)?;
+        },
+        Command::Template { command } => {
+            let mut templates = Templates::load(&config)?;
+            match command {
+                TemplateCommand::Save { name, task_ids, variables } => {
+                    let graph = Graph::load(&config)?;
+                    let task_ids = resolve_task_ids(&task_ids, &graph)?;
+                    let template = graph.to_template(&task_ids, &variables.into_iter().collect())?;
+                    templates.insert(name, template);
+                    templates.save(&config)?;
+                },
+                TemplateCommand::List => {
+                    let template_rows: Vec<TemplateRow> = templates
+                        .iter()
+                        .map(|(name, template)| TemplateRow::new(name, template))
+                        .collect();
+                    println!("{}", Table::new(template_rows));
+                },
+                TemplateCommand::Apply { name, variables } => {
+                    let mut graph = Graph::load(&config)?;
+                    let template = templates.get(&name).ok_or(GraphError::TemplateNotFound)?;
+                    let task_ids = graph.apply_template(template, &variables.into_iter().collect(), Local::now().timestamp())?;
+                    graph.save(&config)?;
+                    for task_id in task_ids {
+                        println!("{task_id}");
+                    }
+                },
+                TemplateCommand::Remove { name } => {
+                    templates.remove(&name).ok_or(GraphError::TemplateNotFound)?;
+                    templates.save(&config)?;
+                },
+            }
+        },
+        Command::Tui => {
+            let graph = Graph::load(&config)?;
+            tui::run(config, graph)?;
+        },
+        Command::Completions { .. } | Command::Complete { .. } | Command::MergeDriver { .. } => unreachable!("Handled before loading config"),
     }
     Ok(())
 }
 
+/// Rows of the todo list, doable tasks first, with tasks in progress ahead of the rest.
+/// Finished tasks are left out. If `all` is false, tasks that are not doable are left out too.
+fn todo_rows(graph: &Graph, all: bool) -> Vec<TaskRow<'_>> {
+    let mut task_rows: Vec<TaskRow> = graph
+        .traverse_selected()
+        .into_iter()
+        .filter(|(_, task)| !task.status.is_finished())
+        .map(|(task_id, task)| TaskRow::new(graph, task_id, task))
+        .filter(|task_row| all || task_row.doable)
+        .collect();
+    task_rows.sort_by_key(|task_row| (
+        !task_row.doable,
+        graph.waits_on_soft_dependencies(task_row.id),
+        task_row.status != TaskStatus::InProgress,
+        task_row.order,
+    ));
+    task_rows
+}
+
+/// Matcher for a search pattern, fuzzy unless `glob` or `regex` is set.
+fn matcher(pattern: String, glob: bool, regex: bool, case_sensitive: bool) -> Result<Matcher> {
+    let matcher = match (glob, regex) {
+        (true, _) => Matcher::glob(&pattern, case_sensitive)?,
+        (_, true) => Matcher::regex(&pattern, case_sensitive)?,
+        _ => Matcher::fuzzy(pattern, case_sensitive),
+    };
+    Ok(matcher)
+}
+
+/// Prints archive rows as a table, or as a list of archive ids when `ids_only` is set.
+fn print_archive_rows(archive_rows: Vec<ArchiveRow>, ids_only: bool) {
+    if ids_only {
+        for archive_row in archive_rows {
+            println!("{}", archive_row.id);
+        }
+    }
+    else {
+        println!("{}", Table::new(archive_rows));
+    }
+}
+
+/// Prints task rows as a table, or as a list of ids when `ids_only` is set.
+fn print_task_rows(task_rows: Vec<TaskRow>, ids_only: bool) {
+    if ids_only {
+        for task_row in task_rows {
+            println!("{}", task_row.id);
+        }
+    }
+    else {
+        let task_table = Table::new(task_rows);
+        println!("{task_table}");
+    }
+}
+
+/// Prints task rows as a table with only the columns specified, in order.
+fn print_task_columns(task_rows: Vec<TaskRow>, columns: &[Column]) {
+    let headers = TaskRow::headers();
+    let indices: Vec<usize> = columns
+        .iter()
+        .filter_map(|column| headers.iter().position(|header| header == column.header()))
+        .collect();
+    let mut builder = Builder::default();
+    builder.push_record(indices.iter().map(|i| headers[*i].to_string()));
+    for task_row in task_rows {
+        let fields = task_row.fields();
+        builder.push_record(indices.iter().map(|i| fields[*i].to_string()));
+    }
+    println!("{}", builder.build());
+}
+
+/// Writes text to a new file in the temp directory, for `tdc edit`.
+/// The file is created exclusively, so that an existing file or symlink is never written through.
+fn create_edit_file(text: &str) -> io::Result<PathBuf> {
+    for attempt in 0.. {
+        let path = env::temp_dir().join(format!("tdc-edit-{}-{attempt}.txt", process::id()));
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(text.as_bytes())?;
+                return Ok(path);
+            },
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!("Ran out of temp file names")
+}
+
+/// One or more task ids given on the command line.
+#[derive(Clone, Debug)]
+enum TaskIds {
+    /// A single task id, ie: 3
+    Single(TaskId),
+    /// A range of task ids, ie: 3..9 or 3..=9
+    Range(Range<TaskId>),
+    /// Task ids read from stdin, separated by whitespace or commas.
+    Stdin,
+}
+
+impl FromStr for TaskIds {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "-" {
+            return Ok(Self::Stdin);
+        }
+        let parse_id = |s: &str| s.trim().parse::<TaskId>().map_err(|_| format!("Invalid task id '{s}'"));
+        if let Some((start, end)) = s.split_once("..=") {
+            let (start, end) = (parse_id(start)?, parse_id(end)?);
+            let end = end.checked_add(1).ok_or_else(|| format!("Invalid range '{s}'"))?;
+            return Ok(Self::Range(start..end));
+        }
+        if let Some((start, end)) = s.split_once("..") {
+            let (start, end) = (parse_id(start)?, parse_id(end)?);
+            return Ok(Self::Range(start..end));
+        }
+        Ok(Self::Single(parse_id(s)?))
+    }
+}
+
+impl TaskIds {
+
+    /// Task ids named by this argument, without reading stdin.
+    /// Ranges only yield tasks that exist in the graph. Single ids are passed through as-is.
+    fn expand(&self, graph: &Graph) -> Vec<TaskId> {
+        match self {
+            TaskIds::Single(task_id) => vec![*task_id],
+            TaskIds::Range(range) => graph.iter().map(|(task_id, _)| task_id).filter(|task_id| range.contains(task_id)).collect(),
+            TaskIds::Stdin => vec![],
+        }
+    }
+}
+
+/// Expands task id arguments into a list of task ids, without duplicates.
+/// Ranges only yield tasks that exist in the graph. Single ids are passed through as-is.
+fn resolve_task_ids(args: &[TaskIds], graph: &Graph) -> Result<Vec<TaskId>> {
+    let mut result = vec![];
+    let mut push = |task_id: TaskId| {
+        if !result.contains(&task_id) {
+            result.push(task_id);
+        }
+    };
+    for arg in args {
+        match arg {
+            TaskIds::Single(_) | TaskIds::Range(_) => arg.expand(graph).into_iter().for_each(&mut push),
+            TaskIds::Stdin => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                let tokens = input.split(|c: char| c.is_whitespace() || c == ',');
+                for token in tokens.filter(|token| !token.is_empty()) {
+                    let task_id = token.parse().map_err(|_| AppError::InvalidTaskId(token.to_owned()))?;
+                    push(task_id);
+                }
+            },
+        }
+    }
+    Ok(result)
+}
+
 struct TaskTree<'a> {
     task_id: TaskId,
     task: &'a Task,
@@ -335,13 +1287,24 @@ struct TaskTree<'a> {
 }
 impl fmt::Display for TaskTree<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        print_task_tree(self.task_id, self.task, self.graph, 0, f)
+        print_task_tree(self.task_id, self.task, TreeLink::Root, self.graph, 0, f)
     }
 }
 
+/// How a task printed by [`print_task_tree`] is linked to the task above it.
+enum TreeLink<'a> {
+    Root,
+    /// Holds the kind and label of the dependency, if not a plain hard dependency.
+    Dependency(Option<&'a DependencyInfo>),
+    Child,
+}
+
+/// Prints a task followed by its dependencies and children, recursively.
+/// Related tasks are printed without their own dependencies.
 fn print_task_tree(
     task_id: TaskId,
     task: &Task,
+    link: TreeLink,
     graph: &Graph,
     indentation: u32,
     f: &mut fmt::Formatter,
@@ -349,10 +1312,25 @@ fn print_task_tree(
     for _ in 0..indentation {
         write!(f, "{}", INDENT)?;
     }
-    writeln!(f, "{}) {}", task_id, task.name)?;
+    write!(f, "{}) {}", task_id, task.name)?;
+    let progress = graph.progress(task_id);
+    if progress.total > 0 {
+        write!(f, " [{}/{}]", progress.finished, progress.total)?;
+    }
+    match link {
+        TreeLink::Dependency(Some(DependencyInfo { kind, label: Some(label) })) => write!(f, " ({kind}: {label})")?,
+        TreeLink::Dependency(Some(DependencyInfo { kind, label: None })) => write!(f, " ({kind})")?,
+        TreeLink::Child => write!(f, " (child)")?,
+        TreeLink::Root | TreeLink::Dependency(None) => {},
+    }
+    writeln!(f)?;
+    if let TreeLink::Dependency(Some(DependencyInfo { kind: DependencyKind::Related, .. })) = link { return Ok(()) }
     for dep_id in task.dependencies().iter().copied() {
         let dep_task = graph.get(dep_id).unwrap();
-        print_task_tree(dep_id, dep_task, graph, indentation + 1, f)?;
+        print_task_tree(dep_id, dep_task, TreeLink::Dependency(task.dependency_info(dep_id)), graph, indentation + 1, f)?;
+    }
+    for (child_id, child) in graph.children(task_id) {
+        print_task_tree(child_id, child, TreeLink::Child, graph, indentation + 1, f)?;
     }
     Ok(())
 }
@@ -362,27 +1340,69 @@ fn print_task_tree(
 #[derive(Tabled)]
 struct TaskRow<'a> {
     id: TaskId,
-    name: &'a str,
+    name: Cow<'a, str>,
     tags: Tags<'a>,
+    status: TaskStatus,
     selected: bool,
     order: TaskOrder,
     dependencies: Dependencies<'a>, 
+    #[tabled(skip)]
+    doable: bool,
 }
 
 impl<'a> TaskRow<'a> {
-    fn new(id: TaskId, task: &'a Task) -> Self {
+    fn new(graph: &Graph, id: TaskId, task: &'a Task) -> Self {
         Self {
             id, 
-            name: &task.name, 
-            tags: Tags(task.tags()),
+            name: Cow::Borrowed(&task.name), 
+            tags: Tags(Cow::Borrowed(task.tags())),
+            status: task.status,
             selected: task.selected,
             order: task.order, 
             dependencies: Dependencies(task.dependencies()),
+            doable: graph.is_doable(id),
+        }
+    }
+
+    /// Indents the name by the depth of the task in the hierarchy, and shows the progress of parent tasks.
+    fn nested(mut self, graph: &Graph, depth: usize) -> Self {
+        let progress = graph.progress(self.id);
+        let mut name = format!("{}{}", INDENT.repeat(depth), self.name);
+        if progress.total > 0 {
+            name = format!("{name} [{}/{}]", progress.finished, progress.total);
+        }
+        self.name = Cow::Owned(name);
+        self
+    }
+
+    /// Highlights the characters of the name and tags that matched a search.
+    fn highlighted(mut self, task_match: &TaskMatch) -> Self {
+        self.name = Cow::Owned(highlight(&self.name, &task_match.name_indices));
+        if !task_match.tag_indices.is_empty() {
+            let mut tags = self.tags.0.into_owned();
+            for (tag_index, indices) in &task_match.tag_indices {
+                tags[*tag_index] = highlight(&tags[*tag_index], indices);
+            }
+            self.tags = Tags(Cow::Owned(tags));
+        }
+        self
+    }
+}
+
+/// Wraps the characters at the given char indices in ANSI bold/yellow escape codes.
+fn highlight(text: &str, indices: &[usize]) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        if indices.contains(&i) {
+            result.push_str(HIGHLIGHT_START);
+            result.push(c);
+            result.push_str(HIGHLIGHT_END);
+        }
+        else {
+            result.push(c);
         }
     }
-     fn doable(&self) -> bool {
-         self.dependencies.0.is_empty()
-     }
+    result
 }
 
 /// Printable list of a task's dependencies
@@ -403,8 +1423,179 @@ impl fmt::Display for Dependencies<'_> {
     }
 }
 
-/// Printable list of a task's dependencies
-struct Tags<'a>(&'a [String]);
+/// Printable archived task record
+#[derive(Tabled)]
+struct ArchiveRow<'a> {
+    id: ArchiveId,
+    name: &'a str,
+    tags: Tags<'a>,
+    status: TaskStatus,
+    #[tabled(display("display_date"))]
+    finished: Option<i64>,
+    #[tabled(display("display_required_date"))]
+    archived: i64,
+}
+
+impl<'a> ArchiveRow<'a> {
+    fn new(id: ArchiveId, entry: &'a ArchivedTask) -> Self {
+        Self {
+            id,
+            name: &entry.task.name,
+            tags: Tags(Cow::Borrowed(entry.task.tags())),
+            status: entry.task.status,
+            finished: entry.task.finished_at,
+            archived: entry.archived_at,
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct TrashRow<'a> {
+    id: TaskId,
+    name: &'a str,
+    status: TaskStatus,
+    dependencies: String,
+    dependents: String,
+    #[tabled(display("display_required_date"))]
+    trashed: i64,
+}
+
+impl<'a> TrashRow<'a> {
+    fn new(trashed: &'a TrashedTask) -> Self {
+        let dependency_ids: Vec<TaskId> = trashed.dependencies.iter().map(|(task_id, _)| *task_id).collect();
+        let dependent_ids: Vec<TaskId> = trashed.dependents.iter().map(|(task_id, _)| *task_id).collect();
+        Self {
+            id: trashed.task_id,
+            name: &trashed.task.name,
+            status: trashed.task.status,
+            dependencies: Dependencies(&dependency_ids).to_string(),
+            dependents: Dependencies(&dependent_ids).to_string(),
+            trashed: trashed.trashed_at,
+        }
+    }
+}
+
+/// Formats an optional unix timestamp as a local date, ie: 2025-01-31.
+fn display_date(timestamp: &Option<i64>) -> String {
+    timestamp.as_ref().map(display_required_date).unwrap_or_default()
+}
+
+/// Formats a unix timestamp as a local date, ie: 2025-01-31.
+fn display_required_date(timestamp: &i64) -> String {
+    Local.timestamp_opt(*timestamp, 0)
+        .single()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Printable dependency record
+#[derive(Tabled)]
+struct DependencyRow<'a> {
+    task: TaskId,
+    dependency: TaskId,
+    kind: DependencyKind,
+    #[tabled(display("display_option"))]
+    label: Option<&'a str>,
+}
+
+/// Printable record of a redundant dependency
+#[derive(Tabled)]
+struct RedundantRow {
+    task: TaskId,
+    dependency: TaskId,
+    implied_by: String,
+}
+
+/// Printable record of the work a task unblocks
+#[derive(Tabled)]
+struct UnblocksRow<'a> {
+    id: TaskId,
+    name: &'a str,
+    direct: usize,
+    total: usize,
+    #[tabled(display("display_duration"))]
+    effort: u64,
+}
+
+/// Printable record of time spent on a task
+#[derive(Tabled)]
+struct TaskTimeRow<'a> {
+    id: TaskId,
+    name: &'a str,
+    #[tabled(display("display_duration"))]
+    time: u64,
+    #[tabled(display("display_duration"))]
+    total: u64,
+}
+
+/// Printable record of time spent on tasks with a tag
+#[derive(Tabled)]
+struct TagTimeRow {
+    tag: String,
+    #[tabled(display("display_duration"))]
+    time: u64,
+}
+
+fn display_duration(seconds: &u64) -> String {
+    format_duration(*seconds)
+}
+
+/// Printable template record
+#[derive(Tabled)]
+struct TemplateRow<'a> {
+    name: &'a str,
+    tasks: usize,
+    variables: String,
+}
+
+impl<'a> TemplateRow<'a> {
+    fn new(name: &'a str, template: &Template) -> Self {
+        let variables: Vec<&str> = template.variables().into_iter().collect();
+        Self { name, tasks: template.tasks.len(), variables: variables.join(",") }
+    }
+}
+
+/// Parses a template variable given as NAME=VALUE.
+fn parse_variable(s: &str) -> std::result::Result<(String, String), String> {
+    let (name, value) = s.split_once('=').ok_or_else(|| format!("Invalid variable '{s}'. Expected NAME=VALUE"))?;
+    if name.is_empty() { return Err(format!("Invalid variable '{s}'. Expected NAME=VALUE")) }
+    Ok((name.to_owned(), value.to_owned()))
+}
+
+/// Printable tag record
+#[derive(Tabled)]
+struct TagRow<'a> {
+    tag: &'a str,
+    tasks: usize,
+    #[tabled(display("display_option"))]
+    description: Option<&'a str>,
+    #[tabled(display("display_option"))]
+    color: Option<&'a str>,
+}
+
+impl<'a> TagRow<'a> {
+    fn new(tag: &'a str, tasks: usize, info: Option<&'a TagInfo>) -> Self {
+        Self {
+            tag,
+            tasks,
+            description: info.and_then(|info| info.description.as_deref()),
+            color: info.and_then(|info| info.color.as_deref()),
+        }
+    }
+}
+
+fn display_option(value: &Option<&str>) -> String {
+    value.unwrap_or_default().to_owned()
+}
+
+/// True if a string is a hex color like "#ff8800".
+fn is_hex_color(color: &str) -> bool {
+    let Some(hex) = color.strip_prefix('#') else { return false };
+    hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Printable list of a task's tags
+struct Tags<'a>(Cow<'a, [String]>);
 impl fmt::Display for Tags<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.0.len() {
@@ -430,7 +1621,62 @@ pub enum AppError {
     #[error(transparent)]
     GlobError(#[from] PatternError),
     #[error(transparent)]
+    RegexError(#[from] regex::Error),
+    #[error(transparent)]
     GraphError(#[from] GraphError),
+    #[error("Invalid task id '{0}'")]
+    InvalidTaskId(String),
+    #[error("Invalid color '{0}'. Expected a hex color like #ff8800")]
+    InvalidColor(String),
+    #[error("View '{0}' not found in config file")]
+    ViewNotFound(String),
+    #[error("Project graph already exists at {}", .0.display())]
+    ProjectExists(PathBuf),
+    #[error("No timer is running")]
+    NoTimerRunning,
+    #[error("Invalid duration '{0}'. Expected something like 45m, 1h30m or 2h")]
+    InvalidDuration(String),
+    #[error("Invalid date '{0}'. Expected today, tomorrow, a weekday, a date like 2025-01-31 or a duration like 3d")]
+    InvalidDate(String),
+    #[error("Task {1} is not a dependency of task {0}, directly or not")]
+    NoPath(TaskId, TaskId),
+    #[error("Editor '{0}' exited with an error")]
+    EditorFailed(String),
+    #[error("{}. Nothing was changed, and your edits were kept in {}", .0, .1.display())]
+    EditRejected(GraphError, PathBuf),
+    #[error("{0} conflict(s) left. Our side was kept for each of them")]
+    MergeConflicts(usize),
+    #[error("Nothing to do. No doable task on the todo list matches")]
+    NothingToDo,
+    #[error("Invalid start of period '{0}'. Expected today, yesterday, a weekday, a date like 2025-01-31 or a duration like 7d")]
+    InvalidSince(String),
+    #[error(transparent)]
+    IOError(#[from] io::Error),
 }
 
 type Result<T> = std::result::Result<T, AppError>;
+
+
+#[cfg(test)]
+mod test {
+    use tdc::{Graph, Task};
+    use crate::{resolve_task_ids, TaskIds};
+
+    #[test]
+    fn test_task_ids() {
+        assert!(matches!("3".parse(), Ok(TaskIds::Single(3))));
+        assert!(matches!(" - ".parse(), Ok(TaskIds::Stdin)));
+        assert!(matches!("2..5".parse::<TaskIds>(), Ok(TaskIds::Range(range)) if range == (2..5)));
+        assert!(matches!("2..=5".parse::<TaskIds>(), Ok(TaskIds::Range(range)) if range == (2..6)));
+        assert!("a..5".parse::<TaskIds>().is_err());
+        assert!(format!("0..={}", usize::MAX).parse::<TaskIds>().is_err());
+
+        let mut graph = Graph::new();
+        for name in ["Ship", "Build", "Test", "Lint"] {
+            graph.insert(Task::new(name));
+        }
+        graph.remove(1);
+        let args: Vec<TaskIds> = ["3", "0..100000000000", "7"].into_iter().map(|arg| arg.parse().unwrap()).collect();
+        assert_eq!(resolve_task_ids(&args, &graph).unwrap(), &[3, 0, 2, 7]);
+    }
+}
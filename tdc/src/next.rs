@@ -0,0 +1,216 @@
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use crate::{format_duration, DependencyKind, Graph, Task, TaskId, TaskOrder, TaskStatus};
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+const SECONDS_PER_HOUR: f64 = 3600.0;
+
+/// Weights of the factors used by `tdc next` to pick a task, stored in the [`Config`](crate::Config).
+/// Each factor is scaled to roughly 0..1 before being weighted. A weight of 0 ignores the factor.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Scoring {
+    /// Favors tasks ordered first.
+    pub order: f64,
+    /// Favors tasks due soon, and overdue tasks most of all.
+    pub due: f64,
+    /// Favors tasks estimated to be quick.
+    pub estimate: f64,
+    /// Favors tasks that unblock other tasks. Counts each task unblocked.
+    pub unblocks: f64,
+    /// Favors tasks created long ago, up to 30 days.
+    pub age: f64,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self { order: 2.0, due: 3.0, estimate: 1.0, unblocks: 1.0, age: 0.5 }
+    }
+}
+
+/// Factor contributing to the score of a [`Recommendation`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Factor {
+    Order,
+    Due,
+    Estimate,
+    Unblocks,
+    Age,
+}
+
+impl fmt::Display for Factor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Factor::Order => "order",
+            Factor::Due => "due",
+            Factor::Estimate => "estimate",
+            Factor::Unblocks => "unblocks",
+            Factor::Age => "age",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Points given to a task by a single [`Factor`], along with a human readable explanation.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Reason {
+    pub factor: Factor,
+    pub points: f64,
+    pub explanation: String,
+}
+
+/// A doable task scored by [`Graph::recommend`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct Recommendation<'a> {
+    pub task_id: TaskId,
+    pub task: &'a Task,
+    pub score: f64,
+    /// Factors that gave the task points, most points first.
+    pub reasons: Vec<Reason>,
+}
+
+/// Limits the tasks considered by [`Graph::recommend`].
+#[derive(Clone, Default, Debug)]
+pub struct NextFilter {
+    /// Only tasks having all of these tags (or their sub-tags) are considered.
+    pub tags: Vec<String>,
+    /// Only tasks that fit in this many seconds are considered. Tasks without an estimate always fit.
+    pub time: Option<u64>,
+}
+
+impl Graph {
+
+    /// Tasks that would become doable once a task is finished.
+    /// That is, its unfinished hard dependents whose other hard dependencies are all finished.
+    pub fn unblocked_by(&self, task_id: TaskId) -> Vec<TaskId> {
+        self.dependents(task_id)
+            .filter(|(_, dependent)| dependent.dependency_kind(task_id) == DependencyKind::Hard)
+            .filter(|(dependent_id, dependent)| !dependent.status.is_finished() && !self.is_doable(*dependent_id))
+            .filter(|(_, dependent)| dependent.status != TaskStatus::Blocked)
+            .filter(|(_, dependent)| dependent
+                .dependencies_of_kind(DependencyKind::Hard)
+                .filter(|dependency_id| *dependency_id != task_id)
+                .all(|dependency_id| self.get(dependency_id).is_none_or(|dependency| dependency.status.is_finished()))
+            )
+            .map(|(dependent_id, _)| dependent_id)
+            .collect()
+    }
+
+    /// Scores the doable tasks of the todo list, best first.
+    pub fn recommend(&self, scoring: &Scoring, filter: &NextFilter, now: i64) -> Vec<Recommendation<'_>> {
+        let candidates: Vec<(TaskId, &Task)> = self
+            .traverse_selected()
+            .into_iter()
+            .filter(|(task_id, _)| self.is_doable(*task_id))
+            .filter(|(_, task)| task.has_all_tags(&filter.tags))
+            .filter(|(_, task)| match (filter.time, task.estimate) {
+                (Some(time), Some(estimate)) => estimate <= time,
+                _ => true,
+            })
+            .collect();
+
+        // Ranks ordered candidates, first being 0
+        let mut ordered: Vec<(TaskOrder, TaskId)> = candidates
+            .iter()
+            .filter(|(_, task)| task.order != TaskOrder::Last)
+            .map(|(task_id, task)| (task.order, *task_id))
+            .collect();
+        ordered.sort();
+
+        let mut recommendations: Vec<Recommendation> = candidates
+            .into_iter()
+            .map(|(task_id, task)| {
+                let mut reasons = vec![];
+                let mut reason = |factor, weight: f64, value: f64, explanation: String| {
+                    if weight != 0.0 && value != 0.0 {
+                        reasons.push(Reason { factor, points: weight * value, explanation });
+                    }
+                };
+                if let Some(rank) = ordered.iter().position(|(_, ordered_id)| *ordered_id == task_id) {
+                    let value = 1.0 - rank as f64 / ordered.len() as f64;
+                    reason(Factor::Order, scoring.order, value, format!("#{} of {} ordered tasks", rank + 1, ordered.len()));
+                }
+                if let Some(due) = task.due {
+                    let days_left = (due - now) as f64 / SECONDS_PER_DAY;
+                    if days_left < 0.0 {
+                        reason(Factor::Due, scoring.due, 1.0, format!("overdue by {}", format_duration((now - due) as u64)));
+                    }
+                    else {
+                        let value = 1.0 / (1.0 + days_left.floor());
+                        reason(Factor::Due, scoring.due, value, format!("due in {}", format_duration((due - now) as u64)));
+                    }
+                }
+                if let Some(estimate) = task.estimate {
+                    let value = 1.0 / (1.0 + estimate as f64 / SECONDS_PER_HOUR);
+                    reason(Factor::Estimate, scoring.estimate, value, format!("estimated at {}", format_duration(estimate)));
+                }
+                let unblocked = self.unblocked_by(task_id).len();
+                reason(Factor::Unblocks, scoring.unblocks, unblocked as f64, format!("unblocks {unblocked} task(s)"));
+                if let Some(created_at) = task.created_at {
+                    let days = ((now - created_at).max(0) as f64 / SECONDS_PER_DAY).floor();
+                    reason(Factor::Age, scoring.age, (days / 30.0).min(1.0), format!("created {days} day(s) ago"));
+                }
+                reasons.sort_by(|a, b| b.points.total_cmp(&a.points));
+                let score = reasons.iter().fold(0.0, |score, reason| score + reason.points);
+                Recommendation { task_id, task, score, reasons }
+            })
+            .collect();
+        recommendations.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.task_id.cmp(&b.task_id)));
+        recommendations
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{Factor, Graph, NextFilter, Scoring, Task, TaskOrder, TaskStatus};
+
+    #[test]
+    fn test_unblocked_by() {
+        let mut graph = Graph::new();
+        let ship_id = graph.insert(Task::new("Ship"));
+        let build_id = graph.insert(Task::new("Build"));
+        let test_id = graph.insert(Task::new("Test"));
+        graph.insert_dependency(ship_id, build_id).unwrap();
+        graph.insert_dependency(ship_id, test_id).unwrap();
+        assert!(graph.unblocked_by(build_id).is_empty());
+        graph.set_status(test_id, TaskStatus::Done, 0).unwrap();
+        assert_eq!(graph.unblocked_by(build_id), &[ship_id]);
+    }
+
+    #[test]
+    fn test_recommend() {
+        let now = 100 * 86400;
+        let mut graph = Graph::new();
+        let mut ship = Task::new("Ship");
+        ship.selected = true;
+        let ship_id = graph.insert(ship);
+        let mut build = Task::new("Build");
+        build.estimate = Some(3 * 3600);
+        build.add_tag("ci".into());
+        let build_id = graph.insert(build);
+        let mut docs = Task::new("Docs");
+        docs.order = TaskOrder::Order(10);
+        docs.estimate = Some(1800);
+        let docs_id = graph.insert(docs);
+        graph.insert_dependency(ship_id, build_id).unwrap();
+        graph.insert_dependency(ship_id, docs_id).unwrap();
+
+        let scoring = Scoring::default();
+        let recommendations = graph.recommend(&scoring, &NextFilter::default(), now);
+        let ids: Vec<_> = recommendations.iter().map(|recommendation| recommendation.task_id).collect();
+        assert_eq!(ids, &[docs_id, build_id]);
+        assert_eq!(recommendations[0].reasons[0].factor, Factor::Order);
+
+        // Overdue tasks jump ahead
+        graph.get_mut(build_id).unwrap().due = Some(now - 60);
+        let recommendations = graph.recommend(&scoring, &NextFilter::default(), now);
+        assert_eq!(recommendations[0].task_id, build_id);
+
+        let filter = NextFilter { time: Some(3600), ..NextFilter::default() };
+        let recommendations = graph.recommend(&scoring, &filter, now);
+        assert_eq!(recommendations.len(), 1);
+        let filter = NextFilter { tags: vec!["ci".into()], ..NextFilter::default() };
+        assert_eq!(graph.recommend(&scoring, &filter, now)[0].task_id, build_id);
+    }
+}
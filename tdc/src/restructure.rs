@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use crate::{Graph, GraphError, Task, TaskId, TaskStatus};
+
+impl Graph {
+
+    /// Merges a task into another, which survives it.
+    /// The survivor gains the tags, dependencies, dependents, children and time entries of the merged task.
+    /// Fails without changing the graph if the merge would form a cycle.
+    pub fn merge(&mut self, survivor_id: TaskId, merged_id: TaskId) -> Result<(), GraphError> {
+        if survivor_id == merged_id { return Err(GraphError::MergeIntoSelf) }
+        self.get(survivor_id).ok_or(GraphError::TaskNotFound)?;
+        let merged = self.get(merged_id).ok_or(GraphError::TaskNotFound)?;
+        let dependencies: Vec<_> = merged.dependencies()
+            .iter()
+            .filter(|dependency_id| **dependency_id != survivor_id)
+            .map(|dependency_id| (*dependency_id, merged.dependency_info(*dependency_id).cloned().unwrap_or_default()))
+            .collect();
+        let dependents: Vec<_> = self.dependents(merged_id)
+            .filter(|(dependent_id, _)| *dependent_id != survivor_id)
+            .map(|(dependent_id, dependent)| (dependent_id, dependent.dependency_info(merged_id).cloned().unwrap_or_default()))
+            .collect();
+        // Children on the way to the survivor, if it is under the merged task, stay where they are
+        let survivor_ancestors = self.ancestors(survivor_id);
+        let children: Vec<TaskId> = self.children(merged_id)
+            .map(|(child_id, _)| child_id)
+            .filter(|child_id| *child_id != survivor_id && !survivor_ancestors.contains(child_id))
+            .collect();
+
+        // Works on a copy, so that a cycle leaves the graph untouched
+        let mut graph = self.clone();
+        if graph.timer.is_some_and(|timer| timer.task_id == merged_id) {
+            graph.timer.as_mut().unwrap().task_id = survivor_id;
+        }
+        graph.remove_dependency(survivor_id, merged_id)?;
+        let merged = graph.remove(merged_id).unwrap();
+        for (dependency_id, info) in dependencies {
+            if graph.get(survivor_id).unwrap().dependencies().contains(&dependency_id) { continue }
+            graph.insert_dependency_with(survivor_id, dependency_id, info)?;
+        }
+        for (dependent_id, info) in dependents {
+            if graph.get(dependent_id).unwrap().dependencies().contains(&survivor_id) { continue }
+            graph.insert_dependency_with(dependent_id, survivor_id, info)?;
+        }
+        for child_id in children {
+            graph.set_parent(child_id, Some(survivor_id))?;
+        }
+        let survivor = graph.get_mut(survivor_id).unwrap();
+        for tag in merged.tags() {
+            survivor.add_tag(tag.clone());
+        }
+        survivor.selected |= merged.selected;
+        survivor.time_entries.extend(merged.time_entries);
+        *self = graph;
+        Ok(())
+    }
+
+    /// Replaces a task with new tasks, one per name.
+    /// Each part inherits the tags, dependencies, dependents and parent of the task.
+    /// Time logged on the task goes to the first part. Returns the ids of the parts.
+    pub fn split(&mut self, task_id: TaskId, names: &[String]) -> Result<Vec<TaskId>, GraphError> {
+        let task = self.get(task_id).ok_or(GraphError::TaskNotFound)?;
+        let dependencies: Vec<_> = task.dependencies()
+            .iter()
+            .map(|dependency_id| (*dependency_id, task.dependency_info(*dependency_id).cloned().unwrap_or_default()))
+            .collect();
+        let dependents: Vec<_> = self.dependents(task_id)
+            .map(|(dependent_id, dependent)| (dependent_id, dependent.dependency_info(task_id).cloned().unwrap_or_default()))
+            .collect();
+        let children: Vec<TaskId> = self.children(task_id).map(|(child_id, _)| child_id).collect();
+        let mut template = Task::new("");
+        template.selected = task.selected;
+        template.status = task.status;
+        template.finished_at = task.finished_at;
+        template.created_at = task.created_at;
+        template.due = task.due;
+        template.order = task.order;
+        template.parent = task.parent;
+        for tag in task.tags() {
+            template.add_tag(tag.clone());
+        }
+        let mut time_entries = task.time_entries.clone();
+
+        let mut part_ids = vec![];
+        for name in names {
+            let mut part = template.clone();
+            part.name = name.clone();
+            part.time_entries = std::mem::take(&mut time_entries);
+            let part_id = self.insert(part);
+            // New tasks have no dependents yet, so none of these can form a cycle
+            for (dependency_id, info) in &dependencies {
+                self.insert_dependency_with(part_id, *dependency_id, info.clone())?;
+            }
+            part_ids.push(part_id);
+        }
+        self.remove(task_id);
+        for (dependent_id, info) in dependents {
+            for part_id in part_ids.iter().copied() {
+                self.insert_dependency_with(dependent_id, part_id, info.clone())?;
+            }
+        }
+        if let Some(first_id) = part_ids.first() {
+            for child_id in children {
+                self.set_parent(child_id, Some(*first_id))?;
+            }
+        }
+        Ok(part_ids)
+    }
+
+    /// Copies a task, keeping its dependencies. If `deep` is set, its transitive dependencies are copied too,
+    /// and the copies depend on each other instead of the originals. Related tasks are never copied.
+    /// Copies start as unselected todo tasks without logged time, created `now`.
+    /// Returns the ids of the original tasks paired with the ids of their copies, the task first.
+    pub fn duplicate(&mut self, task_id: TaskId, deep: bool, now: i64) -> Result<Vec<(TaskId, TaskId)>, GraphError> {
+        let originals: Vec<TaskId> = match deep {
+            true => self.traverse_from(task_id)?.into_iter().map(|(original_id, _)| original_id).collect(),
+            false => {
+                self.get(task_id).ok_or(GraphError::TaskNotFound)?;
+                vec![task_id]
+            },
+        };
+        let mut copies: HashMap<TaskId, TaskId> = HashMap::new();
+        let mut result = vec![];
+        for original_id in originals.iter().copied() {
+            let mut copy = self.get(original_id).unwrap().clone();
+            copy.selected = false;
+            copy.status = TaskStatus::Todo;
+            copy.finished_at = None;
+            copy.created_at = Some(now);
+            copy.time_entries.clear();
+            copy.xy = None;
+            let copy_id = self.insert(copy);
+            copies.insert(original_id, copy_id);
+            result.push((original_id, copy_id));
+        }
+        if deep {
+            for (original_id, copy_id) in result.iter().copied() {
+                let original = self.get(original_id).unwrap();
+                let remapped: Vec<_> = original.dependencies()
+                    .iter()
+                    .filter_map(|dependency_id| Some((*dependency_id, *copies.get(dependency_id)?)))
+                    .map(|(dependency_id, dependency_copy_id)| {
+                        (dependency_id, dependency_copy_id, original.dependency_info(dependency_id).cloned().unwrap_or_default())
+                    })
+                    .collect();
+                let parent = original.parent.and_then(|parent_id| copies.get(&parent_id).copied());
+                for (dependency_id, dependency_copy_id, info) in remapped {
+                    self.remove_dependency(copy_id, dependency_id)?;
+                    self.insert_dependency_with(copy_id, dependency_copy_id, info)?;
+                }
+                if parent.is_some() {
+                    self.set_parent(copy_id, parent)?;
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{Graph, GraphError, Task};
+
+    #[test]
+    fn test_merge() {
+        let mut graph = Graph::new();
+        let ship_id = graph.insert(Task::new("Ship"));
+        let build_id = graph.insert(Task::new("Build"));
+        let mut compile = Task::new("Compile");
+        compile.add_tag("ci".into());
+        let compile_id = graph.insert(compile);
+        let lint_id = graph.insert(Task::new("Lint"));
+        graph.insert_dependency(ship_id, compile_id).unwrap();
+        graph.insert_dependency(build_id, compile_id).unwrap();
+        graph.insert_dependency(compile_id, lint_id).unwrap();
+
+        graph.merge(build_id, compile_id).unwrap();
+        assert!(graph.get(compile_id).is_none());
+        let build = graph.get(build_id).unwrap();
+        assert_eq!(build.dependencies(), &[lint_id]);
+        assert_eq!(build.tags(), &["ci"]);
+        assert_eq!(graph.get(ship_id).unwrap().dependencies(), &[build_id]);
+
+        // Merging a task into one of its transitive dependencies would form a cycle
+        assert!(matches!(graph.merge(lint_id, ship_id), Err(GraphError::CycleDetected(_))));
+        assert!(graph.get(ship_id).is_some());
+        assert!(matches!(graph.merge(lint_id, lint_id), Err(GraphError::MergeIntoSelf)));
+
+        // Merging an epic into one of its children keeps the other children under it
+        let epic_id = graph.insert(Task::new("Epic"));
+        let story_id = graph.insert(Task::new("Story"));
+        let subtask_id = graph.insert(Task::new("Subtask"));
+        let other_id = graph.insert(Task::new("Other story"));
+        graph.set_parent(epic_id, Some(ship_id)).unwrap();
+        graph.set_parent(story_id, Some(epic_id)).unwrap();
+        graph.set_parent(subtask_id, Some(story_id)).unwrap();
+        graph.set_parent(other_id, Some(epic_id)).unwrap();
+        graph.merge(subtask_id, epic_id).unwrap();
+        assert_eq!(graph.get(story_id).unwrap().parent(), Some(ship_id));
+        assert_eq!(graph.get(subtask_id).unwrap().parent(), Some(story_id));
+        assert_eq!(graph.get(other_id).unwrap().parent(), Some(subtask_id));
+    }
+
+    #[test]
+    fn test_split_and_duplicate() {
+        let mut graph = Graph::new();
+        let ship_id = graph.insert(Task::new("Ship"));
+        let docs_id = graph.insert(Task::new("Docs"));
+        let research_id = graph.insert(Task::new("Research"));
+        graph.insert_dependency(ship_id, docs_id).unwrap();
+        graph.insert_dependency(docs_id, research_id).unwrap();
+
+        let part_ids = graph.split(docs_id, &["User guide".into(), "API docs".into()]).unwrap();
+        assert_eq!(part_ids.len(), 2);
+        assert_eq!(graph.get(ship_id).unwrap().dependencies(), &part_ids[..]);
+        for part_id in part_ids.iter().copied() {
+            assert_eq!(graph.get(part_id).unwrap().dependencies(), &[research_id]);
+        }
+
+        let copies = graph.duplicate(ship_id, false, 1000).unwrap();
+        let (_, ship_copy_id) = copies[0];
+        assert_eq!(graph.get(ship_copy_id).unwrap().dependencies(), &part_ids[..]);
+        assert_eq!(graph.get(ship_copy_id).unwrap().created_at, Some(1000));
+
+        let copies = graph.duplicate(ship_id, true, 1000).unwrap();
+        assert_eq!(copies.len(), 4);
+        let copy_of = |task_id| copies.iter().find(|(original_id, _)| *original_id == task_id).unwrap().1;
+        let ship_copy = graph.get(copy_of(ship_id)).unwrap();
+        assert_eq!(ship_copy.dependencies(), &[copy_of(part_ids[0]), copy_of(part_ids[1])]);
+        assert_eq!(graph.get(copy_of(part_ids[0])).unwrap().dependencies(), &[copy_of(research_id)]);
+    }
+}
@@ -0,0 +1,184 @@
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use glob::{MatchOptions, Pattern, PatternError};
+use regex::{Regex, RegexBuilder};
+use crate::{Graph, Task, TaskId};
+
+/// Strategy used to match tasks against a search pattern.
+pub enum Matcher {
+    /// Matches characters of the pattern in order, allowing gaps. Scores tighter matches higher.
+    Fuzzy { pattern: String, matcher: Box<SkimMatcherV2> },
+    /// Matches a regular expression anywhere in the text.
+    Regex(Regex),
+    /// Matches a glob pattern against the entire text.
+    Glob { pattern: Pattern, options: MatchOptions },
+}
+
+impl Matcher {
+
+    pub fn fuzzy(pattern: impl Into<String>, case_sensitive: bool) -> Self {
+        let matcher = SkimMatcherV2::default();
+        let matcher = if case_sensitive { matcher.respect_case() } else { matcher.ignore_case() };
+        Self::Fuzzy { pattern: pattern.into(), matcher: Box::new(matcher) }
+    }
+
+    pub fn regex(pattern: &str, case_sensitive: bool) -> Result<Self, regex::Error> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .build()?;
+        Ok(Self::Regex(regex))
+    }
+
+    pub fn glob(pattern: &str, case_sensitive: bool) -> Result<Self, PatternError> {
+        let pattern = Pattern::new(pattern)?;
+        let options = MatchOptions { case_sensitive, ..MatchOptions::default() };
+        Ok(Self::Glob { pattern, options })
+    }
+
+    /// Matches a single piece of text.
+    /// Returns the score of the match and the char indices of the matched characters.
+    fn match_text(&self, text: &str) -> Option<(i64, Vec<usize>)> {
+        match self {
+            Matcher::Fuzzy { pattern, matcher } => matcher.fuzzy_indices(text, pattern),
+            Matcher::Regex(regex) => {
+                let mut indices = vec![];
+                let mut first_start = None;
+                for m in regex.find_iter(text) {
+                    let start = text[..m.start()].chars().count();
+                    let len = m.as_str().chars().count();
+                    first_start.get_or_insert(start);
+                    indices.extend(start..start + len);
+                }
+                // Earlier matches rank higher
+                let first_start = first_start? as i64;
+                Some((-first_start, indices))
+            },
+            Matcher::Glob { pattern, options } => {
+                if !pattern.matches_with(text, *options) { return None }
+                Some((0, (0..text.chars().count()).collect()))
+            },
+        }
+    }
+
+    /// Matches the task of each item, keeping the items that match, most relevant first, then by id.
+    pub(crate) fn rank<'a, T>(
+        &self,
+        items: impl Iterator<Item = (usize, &'a T)>,
+        task_of: impl Fn(&T) -> &Task,
+    ) -> Vec<(usize, &'a T, TaskMatch)> {
+        let mut result: Vec<(usize, &T, TaskMatch)> = items
+            .filter_map(|(id, item)| Some((id, item, self.match_task(task_of(item))?)))
+            .collect();
+        result.sort_by(|(a_id, _, a), (b_id, _, b)| {
+            (b.in_name, b.score)
+                .cmp(&(a.in_name, a.score))
+                .then(a_id.cmp(b_id))
+        });
+        result
+    }
+
+    /// Matches a task's name and tags.
+    /// Name matches outrank tag matches.
+    pub fn match_task(&self, task: &Task) -> Option<TaskMatch> {
+        let name_match = self.match_text(&task.name);
+        let tag_matches: Vec<(usize, i64, Vec<usize>)> = task
+            .tags()
+            .iter()
+            .enumerate()
+            .filter_map(|(tag_index, tag)| {
+                let (score, indices) = self.match_text(tag)?;
+                Some((tag_index, score, indices))
+            })
+            .collect();
+        let best_tag_score = tag_matches.iter().map(|(_, score, _)| *score).max();
+        let (in_name, score) = match (&name_match, best_tag_score) {
+            (Some((name_score, _)), _) => (true, *name_score),
+            (None, Some(tag_score)) => (false, tag_score),
+            (None, None) => return None,
+        };
+        Some(TaskMatch {
+            in_name,
+            score,
+            name_indices: name_match.map(|(_, indices)| indices).unwrap_or_default(),
+            tag_indices: tag_matches
+                .into_iter()
+                .map(|(tag_index, _, indices)| (tag_index, indices))
+                .collect(),
+        })
+    }
+}
+
+/// Result of matching a task against a [`Matcher`].
+#[derive(Clone, Eq, PartialEq, Default, Debug)]
+pub struct TaskMatch {
+    /// True if the task's name matched.
+    pub in_name: bool,
+    /// Relevance of the match. Higher is better.
+    pub score: i64,
+    /// Char indices of matched characters in the task's name.
+    pub name_indices: Vec<usize>,
+    /// Tag indices paired with the char indices of matched characters in that tag.
+    pub tag_indices: Vec<(usize, Vec<usize>)>,
+}
+
+impl Graph {
+
+    /// Finds all tasks matching the matcher, most relevant first.
+    pub fn search(&self, matcher: &Matcher) -> Vec<(TaskId, &Task, TaskMatch)> {
+        matcher.rank(self.iter(), |task| task)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{Graph, Matcher, Task};
+
+    fn graph() -> Graph {
+        let mut graph = Graph::new();
+        graph.insert(Task::new("Deploy backend"));
+        graph.insert(Task::new("Write docs"));
+        let mut release = Task::new("Cut release");
+        release.add_tag("deploy".into());
+        graph.insert(release);
+        graph.insert(Task::new("Dump old logs"));
+        graph
+    }
+
+    #[test]
+    fn test_fuzzy_search_ranks_name_matches_first() {
+        let graph = graph();
+        let results = graph.search(&Matcher::fuzzy("depl", false));
+        let ids: Vec<_> = results.iter().map(|(task_id, _, _)| *task_id).collect();
+        assert_eq!(ids, &[0, 2]);
+        assert_eq!(results[0].2.name_indices, &[0, 1, 2, 3]);
+        assert_eq!(results[1].2.tag_indices, &[(0, vec![0, 1, 2, 3])]);
+    }
+
+    #[test]
+    fn test_fuzzy_search_allows_gaps() {
+        let graph = graph();
+        let results = graph.search(&Matcher::fuzzy("dpy", false));
+        let ids: Vec<_> = results.iter().map(|(task_id, _, _)| *task_id).collect();
+        assert_eq!(ids, &[0, 2]);
+    }
+
+    #[test]
+    fn test_regex_search() {
+        let graph = graph();
+        let results = graph.search(&Matcher::regex("^d", false).unwrap());
+        let ids: Vec<_> = results.iter().map(|(task_id, _, _)| *task_id).collect();
+        assert_eq!(ids, &[0, 3, 2]);
+        let results = graph.search(&Matcher::regex("^d", true).unwrap());
+        let ids: Vec<_> = results.iter().map(|(task_id, _, _)| *task_id).collect();
+        assert_eq!(ids, &[2]);
+    }
+
+    #[test]
+    fn test_glob_search() {
+        let graph = graph();
+        let results = graph.search(&Matcher::glob("*docs", false).unwrap());
+        let ids: Vec<_> = results.iter().map(|(task_id, _, _)| *task_id).collect();
+        assert_eq!(ids, &[1]);
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use serde::{Serialize, Deserialize};
+use ron::ser::PrettyConfig;
+use crate::{Config, DependencyInfo, Graph, GraphError, Task, TaskId};
+
+/// Named task templates, stored in a file next to the config so every graph can use them.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Templates {
+    templates: BTreeMap<String, Template>,
+}
+
+/// Tasks to create together, along with the dependencies between them.
+/// Task names may contain placeholders, ie: `Release {version}`, filled in when the template is applied.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct Template {
+    pub tasks: Vec<TemplateTask>,
+}
+
+/// A task in a [`Template`]. Other tasks of the template are referred to by index.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct TemplateTask {
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub estimate: Option<u64>,
+    #[serde(default)]
+    pub dependencies: Vec<(usize, DependencyInfo)>,
+    #[serde(default)]
+    pub parent: Option<usize>,
+}
+
+impl Templates {
+
+    pub fn get(&self, name: &str) -> Option<&Template> {
+        self.templates.get(name)
+    }
+
+    /// Inserts a template, replacing any template with the same name.
+    pub fn insert(&mut self, name: String, template: Template) {
+        self.templates.insert(name, template);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Template> {
+        self.templates.remove(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Template)> {
+        self.templates.iter().map(|(name, template)| (name.as_str(), template))
+    }
+
+    pub fn load(config: &Config) -> Result<Templates, GraphError> {
+        let templates_path = config.templates_path();
+        if fs::exists(&templates_path)? {
+            let templates_string = fs::read_to_string(&templates_path)?;
+            let templates = ron::de::from_str(&templates_string).map_err(|_| GraphError::TemplatesParseError)?;
+            Ok(templates)
+        }
+        else {
+            Ok(Templates::default())
+        }
+    }
+
+    pub fn save(&self, config: &Config) -> Result<(), GraphError> {
+        let templates_path = config.templates_path();
+        if let Some(parent_dir) = templates_path.parent() {
+            fs::create_dir_all(parent_dir)?;
+        }
+        let string = ron::ser::to_string_pretty(self, PrettyConfig::default()).expect("Failed to serialize templates");
+        fs::write(&templates_path, string)?;
+        Ok(())
+    }
+}
+
+impl Template {
+
+    /// Names of the placeholders used by the template, ie: `version` for `Release {version}`.
+    pub fn variables(&self) -> BTreeSet<&str> {
+        self.tasks
+            .iter()
+            .flat_map(|task| placeholders(&task.name))
+            .map(|(_, variable)| variable)
+            .collect()
+    }
+}
+
+impl Graph {
+
+    /// Template made of tasks and their transitive dependencies, keeping the dependencies and parents between them.
+    /// Occurrences of each value in `variables` are replaced by a placeholder in task names,
+    /// ie: `version=1.3` turns `Release 1.3` into `Release {version}`.
+    pub fn to_template(&self, task_ids: &[TaskId], variables: &BTreeMap<String, String>) -> Result<Template, GraphError> {
+        let mut included: Vec<TaskId> = vec![];
+        for task_id in task_ids.iter().copied() {
+            for (dependency_id, _) in self.traverse_from(task_id)? {
+                if !included.contains(&dependency_id) {
+                    included.push(dependency_id);
+                }
+            }
+        }
+        let index_of = |task_id: TaskId| included.iter().position(|included_id| *included_id == task_id);
+        let tasks = included
+            .iter()
+            .map(|task_id| {
+                let task = self.get(*task_id).unwrap();
+                let mut name = task.name.clone();
+                for (variable, value) in variables.iter().filter(|(_, value)| !value.is_empty()) {
+                    name = name.replace(value.as_str(), &format!("{{{variable}}}"));
+                }
+                let dependencies = task.dependencies()
+                    .iter()
+                    .filter_map(|dependency_id| {
+                        let info = task.dependency_info(*dependency_id).cloned().unwrap_or_default();
+                        Some((index_of(*dependency_id)?, info))
+                    })
+                    .collect();
+                TemplateTask {
+                    name,
+                    tags: task.tags().to_vec(),
+                    estimate: task.estimate,
+                    dependencies,
+                    parent: task.parent().and_then(index_of),
+                }
+            })
+            .collect();
+        Ok(Template { tasks })
+    }
+
+    /// Creates fresh tasks from a template, filling in placeholders with `variables`.
+    /// Returns the ids of the new tasks, in the order of the template.
+    pub fn apply_template(&mut self, template: &Template, variables: &BTreeMap<String, String>, now: i64) -> Result<Vec<TaskId>, GraphError> {
+        if let Some(missing) = template.variables().into_iter().find(|variable| !variables.contains_key(*variable)) {
+            return Err(GraphError::MissingTemplateVariable(missing.to_owned()));
+        }
+        let mut task_ids = vec![];
+        for template_task in &template.tasks {
+            let mut task = Task::new(fill_placeholders(&template_task.name, variables));
+            task.created_at = Some(now);
+            task.estimate = template_task.estimate;
+            for tag in &template_task.tags {
+                task.add_tag(tag.clone());
+            }
+            task_ids.push(self.insert(task));
+        }
+        for (template_task, task_id) in template.tasks.iter().zip(task_ids.iter().copied()) {
+            for (index, info) in &template_task.dependencies {
+                let dependency_id = *task_ids.get(*index).ok_or(GraphError::TaskNotFound)?;
+                self.insert_dependency_with(task_id, dependency_id, info.clone())?;
+            }
+            if let Some(index) = template_task.parent {
+                self.set_parent(task_id, task_ids.get(index).copied())?;
+            }
+        }
+        Ok(task_ids)
+    }
+}
+
+/// Placeholders in a name, along with their byte range, ie: `{version}`.
+/// Braces around anything but letters, digits, `_` and `-` are not placeholders.
+fn placeholders(name: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let mut result = vec![];
+    let mut start = 0;
+    while let Some(open) = name[start..].find('{').map(|i| start + i) {
+        let Some(close) = name[open..].find('}').map(|i| open + i) else { break };
+        let variable = &name[open + 1..close];
+        let valid = !variable.is_empty() && variable.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+        if valid {
+            result.push((open..close + 1, variable));
+            start = close + 1;
+        }
+        else {
+            start = open + 1;
+        }
+    }
+    result
+}
+
+/// Replaces placeholders in a name with their values, leaving unknown placeholders as they are.
+fn fill_placeholders(name: &str, variables: &BTreeMap<String, String>) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut last = 0;
+    for (range, variable) in placeholders(name) {
+        let Some(value) = variables.get(variable) else { continue };
+        result.push_str(&name[last..range.start]);
+        result.push_str(value);
+        last = range.end;
+    }
+    result.push_str(&name[last..]);
+    result
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use crate::{Graph, GraphError, Task};
+
+    #[test]
+    fn test_templates() {
+        let mut graph = Graph::new();
+        let release_id = graph.insert(Task::new("Release 1.3"));
+        let mut changelog = Task::new("Write changelog for 1.3");
+        changelog.add_tag("docs".into());
+        let changelog_id = graph.insert(changelog);
+        let tag_id = graph.insert(Task::new("Tag commit {not a placeholder}"));
+        graph.insert_dependency(release_id, changelog_id).unwrap();
+        graph.insert_dependency(release_id, tag_id).unwrap();
+        graph.insert_dependency(tag_id, changelog_id).unwrap();
+
+        let variables = BTreeMap::from([("version".to_owned(), "1.3".to_owned())]);
+        let template = graph.to_template(&[release_id], &variables).unwrap();
+        assert_eq!(template.tasks[0].name, "Release {version}");
+        assert_eq!(template.variables().into_iter().collect::<Vec<_>>(), &["version"]);
+
+        assert!(matches!(graph.apply_template(&template, &BTreeMap::new(), 0), Err(GraphError::MissingTemplateVariable(_))));
+        let variables = BTreeMap::from([("version".to_owned(), "1.4".to_owned())]);
+        let task_ids = graph.apply_template(&template, &variables, 0).unwrap();
+        assert_eq!(task_ids.len(), 3);
+        let release = graph.get(task_ids[0]).unwrap();
+        assert_eq!(release.name, "Release 1.4");
+        assert_eq!(release.dependencies().len(), 2);
+        let changelog_copy_id = release.dependencies()[0];
+        let changelog_copy = graph.get(changelog_copy_id).unwrap();
+        assert_eq!(changelog_copy.name, "Write changelog for 1.4");
+        assert_eq!(changelog_copy.tags(), &["docs"]);
+        let tag_copy_id = release.dependencies()[1];
+        assert_eq!(graph.get(tag_copy_id).unwrap().name, "Tag commit {not a placeholder}");
+        assert_eq!(graph.get(tag_copy_id).unwrap().dependencies(), &[changelog_copy_id]);
+    }
+}
@@ -0,0 +1,366 @@
+use std::{fmt, fs};
+use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crate::{display_path, DependencyInfo, Graph, GraphError, Task, TaskId, TrashedTask};
+
+/// Changes made on both sides of a three-way merge that can't be reconciled, found by [`Graph::merge_three_way`].
+/// Our side wins each of them. Task ids are those of the merged graph.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Conflict {
+    /// Both sides changed the same field of a task differently.
+    Field { task_id: TaskId, field: &'static str },
+    /// One side removed a task the other side changed.
+    ChangedAndRemoved { task_id: TaskId },
+    /// Both sides changed the kind or label of the same dependency differently, or one removed it.
+    Dependency { task_id: TaskId, dependency_id: TaskId },
+    /// Their dependency would have formed a cycle with ours, and was dropped.
+    Cycle(Vec<TaskId>),
+    /// Their dependency was from or to a task we removed, and was dropped.
+    DroppedDependency { task_id: TaskId, dependency_id: TaskId },
+    /// Both sides changed the description or color of a tag differently.
+    TagInfo(String),
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Conflict::Field { task_id, field } => write!(f, "{field} of task {task_id} changed on both sides"),
+            Conflict::ChangedAndRemoved { task_id } => write!(f, "task {task_id} changed on one side and removed on the other"),
+            Conflict::Dependency { task_id, dependency_id } => write!(f, "dependency {task_id} -> {dependency_id} changed on both sides"),
+            Conflict::Cycle(path) => write!(f, "dependency dropped, it would form a cycle: {}", display_path(path)),
+            Conflict::DroppedDependency { task_id, dependency_id } => write!(f, "dependency {task_id} -> {dependency_id} dropped, a task was removed"),
+            Conflict::TagInfo(tag) => write!(f, "info of tag '{tag}' changed on both sides"),
+        }
+    }
+}
+
+/// Value changed on either side. None if both sides changed it differently.
+fn merge_value<T: Clone + PartialEq>(base: &T, ours: &T, theirs: &T) -> Option<T> {
+    if ours == theirs || theirs == base {
+        Some(ours.clone())
+    }
+    else if ours == base {
+        Some(theirs.clone())
+    }
+    else {
+        None
+    }
+}
+
+/// Field of a task changed on either side, or ours along with a conflict if both sides changed it.
+fn merge_field<T: Clone + PartialEq>(
+    conflicts: &mut Vec<Conflict>,
+    task_id: TaskId,
+    field: &'static str,
+    [base, ours, theirs]: [&Task; 3],
+    value: impl Fn(&Task) -> T,
+) -> T {
+    merge_value(&value(base), &value(ours), &value(theirs)).unwrap_or_else(|| {
+        conflicts.push(Conflict::Field { task_id, field });
+        value(ours)
+    })
+}
+
+/// True if a task was changed, ignoring its position and links, which also change when other tasks are removed.
+fn is_edited(base: &Task, task: &Task) -> bool {
+    let mut task = task.without_links();
+    task.xy = base.xy;
+    task.collapsed = base.collapsed;
+    task != base.without_links()
+}
+
+impl Graph {
+
+    /// Merges the changes made to a common ancestor on two sides, task by task and field by field.
+    /// Tasks are matched by id. New tasks of their side whose id is already taken on ours get a new id.
+    /// Dependencies are inserted one by one, so the merged graph never has cycles.
+    /// Positions in `todochad` never conflict, ours win.
+    /// Returns the merged graph along with the conflicts found. See [`Conflict`].
+    pub fn merge_three_way(base: &Graph, ours: &Graph, theirs: &Graph) -> (Graph, Vec<Conflict>) {
+        let mut result = ours.clone();
+        let mut conflicts = vec![];
+
+        // Trashed tasks restored on both sides are matched by id, as if they had never been trashed
+        let mut restored_base = base.clone();
+        for trashed in &base.trash {
+            if ours.get(trashed.task_id).is_some() && theirs.get(trashed.task_id).is_some() {
+                restored_base.restore(trashed.task_id).unwrap();
+            }
+        }
+        let base = &restored_base;
+
+        // Trashed tasks they restored or deleted for good leave our trash too
+        result.trash.retain(|trashed| !base.is_trashed(trashed.task_id) || theirs.is_trashed(trashed.task_id));
+
+        // New tasks of theirs keep their id if it is free on our side.
+        // Others get an id used on no side, so it can't be mistaken for a task of the base.
+        let mut id_map: HashMap<TaskId, TaskId> = HashMap::new();
+        let is_free = |graph: &Graph, task_id| graph.get(task_id).is_none() && !graph.is_trashed(task_id);
+        let mut next_id = 0;
+        let mut unused_id = |result: &Graph| {
+            while ![base, ours, theirs, result].into_iter().all(|graph| is_free(graph, next_id)) {
+                next_id += 1;
+            }
+            next_id
+        };
+        let new_tasks: Vec<(TaskId, &Task)> = theirs.iter().filter(|(task_id, _)| base.get(*task_id).is_none()).collect();
+        let new_trashed: Vec<_> = theirs.trash
+            .iter()
+            .filter(|trashed| base.get(trashed.task_id).is_none() && !base.is_trashed(trashed.task_id))
+            .collect();
+        let mut moved_tasks = vec![];
+        for (task_id, task) in new_tasks {
+            if is_free(&result, task_id) {
+                result.insert_at(task_id, task.without_links()).expect("Id checked free");
+                id_map.insert(task_id, task_id);
+            }
+            else {
+                moved_tasks.push((task_id, task));
+            }
+        }
+        let first_new_trashed = result.trash.len();
+        let mut moved_trashed = vec![];
+        for trashed in new_trashed {
+            if is_free(&result, trashed.task_id) {
+                result.trash.push(trashed.clone());
+                id_map.insert(trashed.task_id, trashed.task_id);
+            }
+            else {
+                moved_trashed.push(trashed);
+            }
+        }
+        for (their_id, task) in moved_tasks {
+            let task_id = unused_id(&result);
+            result.insert_at(task_id, task.without_links()).expect("Id checked free");
+            id_map.insert(their_id, task_id);
+        }
+        for trashed in moved_trashed {
+            let task_id = unused_id(&result);
+            id_map.insert(trashed.task_id, task_id);
+            result.trash.push(TrashedTask { task_id, ..trashed.clone() });
+        }
+        let map = |task_id: TaskId| id_map.get(&task_id).copied().unwrap_or(task_id);
+        for trashed in &mut result.trash[first_new_trashed..] {
+            trashed.parent = trashed.parent.map(map);
+            trashed.children.iter_mut().for_each(|child_id| *child_id = map(*child_id));
+            trashed.dependencies.iter_mut().for_each(|(dependency_id, _)| *dependency_id = map(*dependency_id));
+            trashed.dependents.iter_mut().for_each(|(dependent_id, _)| *dependent_id = map(*dependent_id));
+        }
+
+        // Tasks removed on one side are removed from the merge, unless the other side changed them
+        for (task_id, base_task) in base.iter() {
+            match (ours.get(task_id), theirs.get(task_id)) {
+                (Some(our_task), None) if is_edited(base_task, our_task) => conflicts.push(Conflict::ChangedAndRemoved { task_id }),
+                (Some(_), None) => match theirs.trash.iter().find(|trashed| trashed.task_id == task_id) {
+                    Some(trashed) => result.trash(task_id, trashed.trashed_at).unwrap(),
+                    None => { result.remove(task_id); },
+                },
+                (None, Some(their_task)) if is_edited(base_task, their_task) => conflicts.push(Conflict::ChangedAndRemoved { task_id }),
+                _ => {},
+            }
+        }
+
+        // Fields of tasks changed on either side
+        for (task_id, base_task) in base.iter() {
+            let (Some(our_task), Some(their_task)) = (ours.get(task_id), theirs.get(task_id)) else { continue };
+            let Some(task) = result.tasks.get_mut(task_id) else { continue };
+            let tasks = [base_task, our_task, their_task];
+            task.name = merge_field(&mut conflicts, task_id, "name", tasks, |task| task.name.clone());
+            task.selected = merge_field(&mut conflicts, task_id, "selected", tasks, |task| task.selected);
+            (task.status, task.finished_at) = merge_field(&mut conflicts, task_id, "status", tasks, |task| (task.status, task.finished_at));
+            task.created_at = merge_field(&mut conflicts, task_id, "created_at", tasks, |task| task.created_at);
+            task.due = merge_field(&mut conflicts, task_id, "due", tasks, |task| task.due);
+            task.estimate = merge_field(&mut conflicts, task_id, "estimate", tasks, |task| task.estimate);
+            task.order = merge_field(&mut conflicts, task_id, "order", tasks, |task| task.order);
+            task.xy = merge_value(&base_task.xy, &our_task.xy, &their_task.xy).unwrap_or(our_task.xy);
+            task.collapsed = merge_value(&base_task.collapsed, &our_task.collapsed, &their_task.collapsed).unwrap_or(our_task.collapsed);
+            for entry in &their_task.time_entries {
+                if !task.time_entries.contains(entry) {
+                    task.time_entries.push(*entry);
+                }
+            }
+            for tag in their_task.tags() {
+                if !base_task.tags().contains(tag) {
+                    task.add_tag(tag.clone());
+                }
+            }
+            for tag in base_task.tags() {
+                if !their_task.tags().contains(tag) {
+                    task.remove_tag(tag);
+                }
+            }
+        }
+
+        // Dependencies changed on either side, removals first so that reversed dependencies don't form cycles
+        let dependencies = |task: Option<&Task>, map: &dyn Fn(TaskId) -> TaskId| -> BTreeMap<TaskId, DependencyInfo> {
+            task.into_iter()
+                .flat_map(|task| task.dependencies().iter().map(|dependency_id| {
+                    (map(*dependency_id), task.dependency_info(*dependency_id).cloned().unwrap_or_default())
+                }))
+                .collect()
+        };
+        let mut removals = vec![];
+        let mut insertions = vec![];
+        for (their_id, their_task) in theirs.iter() {
+            let task_id = map(their_id);
+            let (base_task, our_task) = match base.get(their_id) {
+                Some(base_task) => (Some(base_task), ours.get(their_id)),
+                None => (None, None),
+            };
+            let base_dependencies = dependencies(base_task, &|task_id| task_id);
+            let our_dependencies = dependencies(our_task, &|task_id| task_id);
+            let their_dependencies = dependencies(Some(their_task), &map);
+            let dependency_ids: BTreeSet<TaskId> = base_dependencies.keys().chain(our_dependencies.keys()).chain(their_dependencies.keys()).copied().collect();
+            for dependency_id in dependency_ids {
+                // Dependencies on tasks they removed go along with those tasks
+                if base.get(dependency_id).is_some() && theirs.get(dependency_id).is_none() { continue }
+                let our_info = our_dependencies.get(&dependency_id).cloned();
+                match merge_value(&base_dependencies.get(&dependency_id).cloned(), &our_info, &their_dependencies.get(&dependency_id).cloned()) {
+                    None => conflicts.push(Conflict::Dependency { task_id, dependency_id }),
+                    Some(info) if info == our_info => {},
+                    Some(None) => removals.push((task_id, dependency_id)),
+                    Some(Some(info)) => insertions.push((task_id, dependency_id, info)),
+                }
+            }
+        }
+        for (task_id, dependency_id) in removals {
+            result.remove_dependency(task_id, dependency_id).ok();
+        }
+        for (task_id, dependency_id, info) in insertions {
+            match result.insert_dependency_with(task_id, dependency_id, info) {
+                Ok(()) => {},
+                Err(GraphError::CycleDetected(path)) => conflicts.push(Conflict::Cycle(path)),
+                Err(_) => conflicts.push(Conflict::DroppedDependency { task_id, dependency_id }),
+            }
+        }
+
+        // Parents changed on either side, once every task is in place
+        for (their_id, their_task) in theirs.iter() {
+            let task_id = map(their_id);
+            let Some(task) = result.get(task_id) else { continue };
+            let (base_parent, our_parent) = match base.get(their_id) {
+                Some(base_task) => (base_task.parent, ours.get(their_id).and_then(|our_task| our_task.parent)),
+                None => (None, None),
+            };
+            let Some(parent) = merge_value(&base_parent, &our_parent, &their_task.parent.map(map)) else {
+                conflicts.push(Conflict::Field { task_id, field: "parent" });
+                continue
+            };
+            if parent != task.parent && result.set_parent(task_id, parent).is_err() {
+                conflicts.push(Conflict::Field { task_id, field: "parent" });
+            }
+        }
+
+        let tags: BTreeSet<String> = [base, ours, theirs].into_iter().flat_map(|graph| graph.tag_infos.keys().cloned()).collect();
+        for tag in tags {
+            let info = |graph: &Graph| graph.tag_infos.get(&tag).cloned();
+            match merge_value(&info(base), &info(ours), &info(theirs)) {
+                Some(Some(info)) => { result.tag_infos.insert(tag, info); },
+                Some(None) => { result.tag_infos.remove(&tag); },
+                None => conflicts.push(Conflict::TagInfo(tag)),
+            }
+        }
+        (result, conflicts)
+    }
+
+    /// Loads the common ancestor of a merge.
+    /// Git passes an empty file when both sides added the graph, which is the same as an empty graph.
+    pub fn load_merge_base(path: &Path) -> Result<Graph, GraphError> {
+        if fs::read_to_string(path)?.trim().is_empty() {
+            return Ok(Graph::default())
+        }
+        Graph::load_file(path)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+    use crate::{Conflict, Graph, Task};
+
+    #[test]
+    fn test_merge_three_way() {
+        let mut base = Graph::new();
+        let ship_id = base.insert(Task::new("Ship"));
+        let build_id = base.insert(Task::new("Build"));
+        let docs_id = base.insert(Task::new("Docs"));
+        let lint_id = base.insert(Task::new("Lint"));
+        base.insert_dependency(ship_id, build_id).unwrap();
+
+        let mut ours = base.clone();
+        ours.get_mut(ship_id).unwrap().name = "Ship it".into();
+        ours.get_mut(docs_id).unwrap().name = "Write docs".into();
+        let test_id = ours.insert(Task::new("Test"));
+        ours.insert_dependency(build_id, docs_id).unwrap();
+
+        let mut theirs = base.clone();
+        theirs.get_mut(build_id).unwrap().add_tag("ci".into());
+        theirs.get_mut(docs_id).unwrap().name = "Docs site".into();
+        let package_id = theirs.insert(Task::new("Package"));
+        theirs.insert_dependency(ship_id, package_id).unwrap();
+        theirs.insert_dependency(docs_id, ship_id).unwrap();
+        theirs.trash(lint_id, 0).unwrap();
+
+        let (merged, conflicts) = Graph::merge_three_way(&base, &ours, &theirs);
+        assert_eq!(merged.get(ship_id).unwrap().name, "Ship it");
+        assert_eq!(merged.get(build_id).unwrap().tags(), &["ci"]);
+        assert_eq!(merged.get(test_id).unwrap().name, "Test");
+        assert!(merged.is_trashed(lint_id));
+
+        // Their new task collided with ours and got a new id
+        let (package_id, _) = merged.iter().find(|(_, task)| task.name == "Package").unwrap();
+        assert!(package_id != test_id);
+        assert_eq!(merged.get(ship_id).unwrap().dependencies(), &[build_id, package_id]);
+
+        // Ours win conflicts, and their dependency closing a cycle is dropped
+        assert_eq!(merged.get(docs_id).unwrap().name, "Write docs");
+        assert!(merged.get(docs_id).unwrap().dependencies().is_empty());
+        assert_eq!(conflicts, &[
+            Conflict::Field { task_id: docs_id, field: "name" },
+            Conflict::Cycle(vec![docs_id, ship_id, build_id, docs_id]),
+        ]);
+    }
+
+    #[test]
+    fn test_merge_restored_and_reused_ids() {
+        let mut base = Graph::new();
+        let ship_id = base.insert(Task::new("Ship"));
+        let build_id = base.insert(Task::new("Build"));
+        let docs_id = base.insert(Task::new("Docs"));
+        base.trash(docs_id, 0).unwrap();
+
+        // Both sides restored the same task, and ours removed one for good after adding another
+        let mut ours = base.clone();
+        ours.restore(docs_id).unwrap();
+        let test_id = ours.insert(Task::new("Test"));
+        ours.remove(build_id);
+        let mut theirs = base.clone();
+        theirs.restore(docs_id).unwrap();
+        theirs.get_mut(docs_id).unwrap().name = "User guide".into();
+        let package_id = theirs.insert(Task::new("Package"));
+        theirs.insert_dependency(ship_id, build_id).unwrap();
+
+        let (merged, conflicts) = Graph::merge_three_way(&base, &ours, &theirs);
+        assert_eq!(merged.iter().filter(|(_, task)| task.name == "User guide").count(), 1);
+        assert_eq!(merged.get(docs_id).unwrap().name, "User guide");
+        assert!(merged.trashed().is_empty());
+
+        // Their new task doesn't take the id ours freed, which their changes still refer to
+        assert_eq!(package_id, test_id);
+        let (package_id, _) = merged.iter().find(|(_, task)| task.name == "Package").unwrap();
+        assert!(package_id != build_id && package_id != test_id);
+        assert!(merged.get(ship_id).unwrap().dependencies().is_empty());
+        assert_eq!(conflicts, &[Conflict::DroppedDependency { task_id: ship_id, dependency_id: build_id }]);
+    }
+
+    #[test]
+    fn test_load_empty_merge_base() {
+        let path = env::temp_dir().join(format!("tdc_test_load_empty_merge_base_{}.ron", std::process::id()));
+        fs::write(&path, " \n").unwrap();
+        let base = Graph::load_merge_base(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(base.unwrap().is_empty());
+        assert!(Graph::load_merge_base(&path).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, TimeZone, Weekday};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use crate::{tag_key, Graph, GraphError, TaskId, TAG_SEPARATOR};
+
+/// Time spent on a task.
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
+pub struct TimeEntry {
+    /// Unix timestamp, in seconds, of when the work started.
+    pub start: i64,
+    /// Duration of the work, in seconds.
+    pub seconds: u64,
+}
+
+/// A running timer started with `tdc start`.
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Timer {
+    pub task_id: TaskId,
+    /// Unix timestamp, in seconds, of when the timer started.
+    pub start: i64,
+}
+
+impl Graph {
+
+    /// Starts a timer on a task, stopping the running one if any.
+    /// Returns the time entry of the stopped timer.
+    pub fn start_timer(&mut self, task_id: TaskId, now: i64) -> Result<Option<(TaskId, TimeEntry)>, GraphError> {
+        self.get(task_id).ok_or(GraphError::TaskNotFound)?;
+        let stopped = self.stop_timer(now);
+        self.timer = Some(Timer { task_id, start: now });
+        Ok(stopped)
+    }
+
+    /// Stops the running timer, logging the time spent on its task.
+    /// Returns the task and its new time entry, or None if no timer was running.
+    pub fn stop_timer(&mut self, now: i64) -> Option<(TaskId, TimeEntry)> {
+        let timer = self.timer.take()?;
+        let seconds = (now - timer.start).max(0) as u64;
+        let entry = TimeEntry { start: timer.start, seconds };
+        self.get_mut(timer.task_id)?.time_entries.push(entry);
+        Some((timer.task_id, entry))
+    }
+
+    /// The running timer, if any.
+    pub fn timer(&self) -> Option<Timer> {
+        self.timer
+    }
+
+    /// Logs time spent on a task, ending now.
+    /// Fails if the work would have started before the earliest timestamp.
+    pub fn log_time(&mut self, task_id: TaskId, seconds: u64, now: i64) -> Result<(), GraphError> {
+        let start = i64::try_from(seconds)
+            .ok()
+            .and_then(|seconds| now.checked_sub(seconds))
+            .ok_or(GraphError::DurationTooLong)?;
+        let task = self.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
+        task.time_entries.push(TimeEntry { start, seconds });
+        Ok(())
+    }
+
+    /// Seconds spent on a task, counting entries started at or after `since`.
+    pub fn time_spent(&self, task_id: TaskId, since: i64) -> Result<u64, GraphError> {
+        let task = self.get(task_id).ok_or(GraphError::TaskNotFound)?;
+        let seconds = task.time_entries
+            .iter()
+            .filter(|entry| entry.start >= since)
+            .map(|entry| entry.seconds)
+            .sum();
+        Ok(seconds)
+    }
+
+    /// Seconds spent on a task and all of its transitive dependencies, counting entries started at or after `since`.
+    /// Each dependency is counted once, even if reachable through multiple paths.
+    pub fn rolled_up_time(&self, task_id: TaskId, since: i64) -> Result<u64, GraphError> {
+        let mut seconds = 0;
+        for (dependency_id, _) in self.traverse_from(task_id)? {
+            seconds += self.time_spent(dependency_id, since)?;
+        }
+        Ok(seconds)
+    }
+
+    /// Seconds spent on tasks of each tag, counting entries started at or after `since`.
+    /// Time counts towards each tag of a task and their parent tags, once per task.
+    /// Tags differing only by case are grouped, under the spelling met first. Sorted by tag.
+    pub fn time_by_tag(&self, since: i64) -> Result<Vec<(String, u64)>, GraphError> {
+        let mut tag_times: BTreeMap<String, (String, u64)> = BTreeMap::new();
+        for (task_id, task) in self.iter() {
+            let time = self.time_spent(task_id, since)?;
+            if time == 0 { continue }
+            let mut tags = BTreeMap::new();
+            for tag in task.tags() {
+                let mut end = tag.len();
+                loop {
+                    let tag = &tag[..end];
+                    tags.entry(tag_key(tag)).or_insert(tag);
+                    match tag.rfind(TAG_SEPARATOR) {
+                        Some(separator) => end = separator,
+                        None => break,
+                    }
+                }
+            }
+            for (key, tag) in tags {
+                let (_, tag_time) = tag_times.entry(key).or_insert((tag.to_owned(), 0));
+                *tag_time += time;
+            }
+        }
+        Ok(tag_times.into_values().collect())
+    }
+}
+
+/// Parses a duration like "45m", "1h30m", "2h", "90s" or "1d".
+/// A bare number is treated as minutes. Durations too long to fit in a `u64` are rejected.
+pub fn parse_duration(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if let Ok(minutes) = s.parse::<u64>() {
+        return minutes.checked_mul(60);
+    }
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'd' => 86400.0,
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => return None,
+        };
+        let value: f64 = number.parse().ok()?;
+        let value = (value * unit).round();
+        // Converting to u64 saturates, so values out of range are rejected beforehand
+        if value >= u64::MAX as f64 { return None }
+        seconds = seconds.checked_add(value as u64)?;
+        number.clear();
+    }
+    number.is_empty().then_some(seconds)
+}
+
+/// Formats seconds as a duration like "1h30m".
+pub fn format_duration(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    match (hours, minutes) {
+        (0, 0) if seconds > 0 => format!("{seconds}s"),
+        (0, minutes) => format!("{minutes}m"),
+        (hours, 0) => format!("{hours}h"),
+        (hours, minutes) => format!("{hours}h{minutes}m"),
+    }
+}
+
+/// Parses the start of a reporting period, relative to `now`.
+/// Accepts "today", "yesterday", a weekday like "monday" (the most recent one, including today),
+/// a date like "2025-01-31", or a duration like "7d" meaning that long ago.
+pub fn parse_since(s: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let s = s.trim().to_lowercase();
+    let today = now.date_naive();
+    let date = match s.as_str() {
+        "today" => today,
+        "yesterday" => today.checked_sub_days(Days::new(1))?,
+        _ => {
+            if let Ok(weekday) = s.parse::<Weekday>() {
+                let days_back = (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+                today.checked_sub_days(Days::new(days_back as u64))?
+            }
+            else if let Ok(date) = NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+                date
+            }
+            else {
+                let seconds = parse_duration(&s)?;
+                return now.checked_sub_signed(chrono::Duration::try_seconds(i64::try_from(seconds).ok()?)?);
+            }
+        },
+    };
+    Local.from_local_datetime(&date.and_time(NaiveTime::MIN)).earliest()
+}
+
+/// Parses a due date, relative to `now`. The task is due at the end of that day.
+/// Accepts "today", "tomorrow", a weekday like "friday" (the next one, including today),
+/// a date like "2025-01-31", or a duration like "3d" meaning that long from now.
+pub fn parse_due(s: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let s = s.trim().to_lowercase();
+    let today = now.date_naive();
+    let date = match s.as_str() {
+        "today" => today,
+        "tomorrow" => today.checked_add_days(Days::new(1))?,
+        _ => {
+            if let Ok(weekday) = s.parse::<Weekday>() {
+                let days_ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+                today.checked_add_days(Days::new(days_ahead as u64))?
+            }
+            else if let Ok(date) = NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+                date
+            }
+            else {
+                let seconds = parse_duration(&s)?;
+                return now.checked_add_signed(chrono::Duration::try_seconds(i64::try_from(seconds).ok()?)?);
+            }
+        },
+    };
+    let end_of_day = NaiveTime::from_hms_opt(23, 59, 59)?;
+    Local.from_local_datetime(&date.and_time(end_of_day)).latest()
+}
+
+
+#[cfg(test)]
+mod test {
+    use chrono::{Local, TimeZone};
+    use crate::{format_duration, parse_due, parse_duration, parse_since, Graph, GraphError, Task};
+
+    #[test]
+    fn test_parse_and_format_duration() {
+        assert_eq!(parse_duration("45m"), Some(45 * 60));
+        assert_eq!(parse_duration("1h30m"), Some(90 * 60));
+        assert_eq!(parse_duration("1.5h"), Some(90 * 60));
+        assert_eq!(parse_duration("20"), Some(20 * 60));
+        assert_eq!(parse_duration("2x"), None);
+        assert_eq!(parse_duration("5h3"), None);
+        assert_eq!(parse_duration("999999999999999999"), None);
+        assert_eq!(parse_duration("99999999999999999999h99999999999999999999h"), None);
+        assert_eq!(parse_duration("5000000000000000h5000000000000000h"), None);
+        assert_eq!(format_duration(90 * 60), "1h30m");
+        assert_eq!(format_duration(2 * 3600), "2h");
+        assert_eq!(format_duration(45 * 60), "45m");
+        assert_eq!(format_duration(0), "0m");
+    }
+
+    #[test]
+    fn test_parse_since() {
+        // 2025-01-15 is a Wednesday
+        let now = Local.with_ymd_and_hms(2025, 1, 15, 14, 30, 0).unwrap();
+        let midnight = |day| Local.with_ymd_and_hms(2025, 1, day, 0, 0, 0).unwrap();
+        assert_eq!(parse_since("today", now), Some(midnight(15)));
+        assert_eq!(parse_since("yesterday", now), Some(midnight(14)));
+        assert_eq!(parse_since("monday", now), Some(midnight(13)));
+        assert_eq!(parse_since("wednesday", now), Some(midnight(15)));
+        assert_eq!(parse_since("thu", now), Some(midnight(9)));
+        assert_eq!(parse_since("2025-01-02", now), Some(midnight(2)));
+        assert_eq!(parse_since("1d", now), Some(Local.with_ymd_and_hms(2025, 1, 14, 14, 30, 0).unwrap()));
+        assert_eq!(parse_since("whenever", now), None);
+        assert_eq!(parse_since("99999999999999d", now), None);
+    }
+
+    #[test]
+    fn test_parse_due() {
+        // 2025-01-15 is a Wednesday
+        let now = Local.with_ymd_and_hms(2025, 1, 15, 14, 30, 0).unwrap();
+        let end_of_day = |day| Local.with_ymd_and_hms(2025, 1, day, 23, 59, 59).unwrap();
+        assert_eq!(parse_due("today", now), Some(end_of_day(15)));
+        assert_eq!(parse_due("tomorrow", now), Some(end_of_day(16)));
+        assert_eq!(parse_due("friday", now), Some(end_of_day(17)));
+        assert_eq!(parse_due("tue", now), Some(end_of_day(21)));
+        assert_eq!(parse_due("2025-01-31", now), Some(end_of_day(31)));
+        assert_eq!(parse_due("2h", now), Some(Local.with_ymd_and_hms(2025, 1, 15, 16, 30, 0).unwrap()));
+        assert_eq!(parse_due("soon", now), None);
+    }
+
+    #[test]
+    fn test_timer_and_rollup() {
+        let mut graph = Graph::new();
+        let release_id = graph.insert(Task::new("Release"));
+        let build_id = graph.insert(Task::new("Build"));
+        let test_id = graph.insert(Task::new("Test"));
+        graph.insert_dependency(release_id, build_id).unwrap();
+        graph.insert_dependency(release_id, test_id).unwrap();
+        graph.insert_dependency(test_id, build_id).unwrap();
+
+        graph.start_timer(build_id, 1000).unwrap();
+        let stopped = graph.start_timer(test_id, 1600).unwrap();
+        assert_eq!(stopped.map(|(task_id, entry)| (task_id, entry.seconds)), Some((build_id, 600)));
+        assert_eq!(graph.stop_timer(1900).map(|(_, entry)| entry.seconds), Some(300));
+        assert_eq!(graph.stop_timer(2000), None);
+        graph.log_time(release_id, 60, 5000).unwrap();
+
+        assert_eq!(graph.time_spent(build_id, 0).unwrap(), 600);
+        assert_eq!(graph.rolled_up_time(test_id, 0).unwrap(), 900);
+        assert_eq!(graph.rolled_up_time(release_id, 0).unwrap(), 960);
+        assert_eq!(graph.rolled_up_time(release_id, 1500).unwrap(), 360);
+        assert!(matches!(graph.log_time(release_id, u64::MAX, 5000), Err(GraphError::DurationTooLong)));
+        assert!(matches!(graph.log_time(release_id, i64::MAX as u64, -5000), Err(GraphError::DurationTooLong)));
+    }
+
+    #[test]
+    fn test_time_by_tag() {
+        let mut graph = Graph::new();
+        let mut backend = Task::new("Backend");
+        backend.add_tag("work/backend".into());
+        backend.add_tag("urgent".into());
+        let backend_id = graph.insert(backend);
+        let mut frontend = Task::new("Frontend");
+        frontend.add_tag("Work/frontend".into());
+        let frontend_id = graph.insert(frontend);
+        let mut idle = Task::new("Idle");
+        idle.add_tag("someday".into());
+        graph.insert(idle);
+        graph.log_time(backend_id, 600, 1000).unwrap();
+        graph.log_time(frontend_id, 300, 5000).unwrap();
+
+        assert_eq!(graph.time_by_tag(0).unwrap(), &[
+            ("urgent".into(), 600),
+            ("work".into(), 900),
+            ("work/backend".into(), 600),
+            ("Work/frontend".into(), 300),
+        ]);
+        assert_eq!(graph.time_by_tag(1000).unwrap(), &[("Work".into(), 300), ("Work/frontend".into(), 300)]);
+    }
+}
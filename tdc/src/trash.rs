@@ -0,0 +1,148 @@
+use serde::{Serialize, Deserialize};
+use crate::{DependencyInfo, Graph, GraphError, Task, TaskId};
+
+/// A task removed from the graph, along with the links it had, so it can be restored.
+/// Its id stays reserved until the trash is emptied.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TrashedTask {
+    pub task_id: TaskId,
+    /// The task, without its dependencies or parent.
+    pub task: Task,
+    /// Tasks the task depended on.
+    pub dependencies: Vec<(TaskId, DependencyInfo)>,
+    /// Tasks that depended on the task.
+    pub dependents: Vec<(TaskId, DependencyInfo)>,
+    pub parent: Option<TaskId>,
+    pub children: Vec<TaskId>,
+    /// Unix timestamp, in seconds, of when the task was trashed.
+    pub trashed_at: i64,
+}
+
+impl Graph {
+
+    /// Removes a task, moving it to the trash along with the links it had.
+    pub fn trash(&mut self, task_id: TaskId, now: i64) -> Result<(), GraphError> {
+        let task = self.get(task_id).ok_or(GraphError::TaskNotFound)?;
+        let dependencies = task.dependencies()
+            .iter()
+            .map(|dependency_id| (*dependency_id, task.dependency_info(*dependency_id).cloned().unwrap_or_default()))
+            .collect();
+        let dependents = self.dependents(task_id)
+            .map(|(dependent_id, dependent)| (dependent_id, dependent.dependency_info(task_id).cloned().unwrap_or_default()))
+            .collect();
+        let parent = task.parent;
+        let children = self.children(task_id).map(|(child_id, _)| child_id).collect();
+        let task = self.remove_keeping_id(task_id).unwrap();
+        self.trash.push(TrashedTask { task_id, task, dependencies, dependents, parent, children, trashed_at: now });
+        Ok(())
+    }
+
+    /// Tasks in the trash, oldest first.
+    pub fn trashed(&self) -> &[TrashedTask] {
+        &self.trash
+    }
+
+    /// True if a task is in the trash.
+    pub fn is_trashed(&self, task_id: TaskId) -> bool {
+        self.trash.iter().any(|trashed| trashed.task_id == task_id)
+    }
+
+    /// Moves a task out of the trash, with the same id, and restores the links it had.
+    /// Links to tasks still in the trash are restored along with those tasks.
+    /// Links to tasks that are gone, or that would now form a cycle, are dropped.
+    pub fn restore(&mut self, task_id: TaskId) -> Result<(), GraphError> {
+        let index = self.trash
+            .iter()
+            .position(|trashed| trashed.task_id == task_id)
+            .ok_or(GraphError::TrashedTaskNotFound)?;
+        self.insert_at(task_id, self.trash[index].task.clone())?;
+        let trashed = self.trash.remove(index);
+
+        for (dependency_id, info) in trashed.dependencies {
+            if let Some(other) = self.trash.iter_mut().find(|other| other.task_id == dependency_id) {
+                other.dependents.push((task_id, info));
+            }
+            else if self.get(dependency_id).is_some() {
+                self.insert_dependency_with(task_id, dependency_id, info).ok();
+            }
+        }
+        for (dependent_id, info) in trashed.dependents {
+            if let Some(other) = self.trash.iter_mut().find(|other| other.task_id == dependent_id) {
+                other.dependencies.push((task_id, info));
+            }
+            else if self.get(dependent_id).is_some() {
+                self.insert_dependency_with(dependent_id, task_id, info).ok();
+            }
+        }
+        if let Some(parent_id) = trashed.parent {
+            if let Some(other) = self.trash.iter_mut().find(|other| other.task_id == parent_id) {
+                other.children.push(task_id);
+            }
+            else {
+                self.set_parent(task_id, Some(parent_id)).ok();
+            }
+        }
+        for child_id in trashed.children {
+            if let Some(other) = self.trash.iter_mut().find(|other| other.task_id == child_id) {
+                other.parent = Some(task_id);
+            }
+            else {
+                self.set_parent(child_id, Some(task_id)).ok();
+            }
+        }
+        Ok(())
+    }
+
+    /// Permanently deletes the tasks in the trash, freeing their ids.
+    /// Returns the number of tasks deleted.
+    pub fn empty_trash(&mut self) -> usize {
+        let count = self.trash.len();
+        self.trash.clear();
+        count
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{DependencyInfo, DependencyKind, Graph, GraphError, Task};
+
+    #[test]
+    fn test_trash_and_restore() {
+        let mut graph = Graph::new();
+        let ship_id = graph.insert(Task::new("Ship"));
+        let build_id = graph.insert(Task::new("Build"));
+        let compile_id = graph.insert(Task::new("Compile"));
+        let docs_id = graph.insert(Task::new("Docs"));
+        graph.insert_dependency(ship_id, build_id).unwrap();
+        graph.insert_dependency(build_id, compile_id).unwrap();
+        let related = DependencyInfo { kind: DependencyKind::Related, label: None };
+        graph.insert_dependency_with(docs_id, build_id, related).unwrap();
+        graph.set_parent(compile_id, Some(build_id)).unwrap();
+
+        graph.trash(build_id, 0).unwrap();
+        graph.trash(compile_id, 0).unwrap();
+        assert!(graph.get(ship_id).unwrap().dependencies().is_empty());
+        assert!(graph.is_trashed(build_id));
+
+        // Ids of trashed tasks are not reused
+        let lint_id = graph.insert(Task::new("Lint"));
+        assert!(lint_id != build_id && lint_id != compile_id);
+
+        graph.restore(build_id).unwrap();
+        assert_eq!(graph.get(build_id).unwrap().name, "Build");
+        assert_eq!(graph.get(ship_id).unwrap().dependencies(), &[build_id]);
+        assert_eq!(graph.get(docs_id).unwrap().dependency_kind(build_id), DependencyKind::Related);
+        assert!(graph.get(build_id).unwrap().dependencies().is_empty());
+
+        // Links to tasks restored later come back with them
+        graph.restore(compile_id).unwrap();
+        assert_eq!(graph.get(build_id).unwrap().dependencies(), &[compile_id]);
+        assert_eq!(graph.get(compile_id).unwrap().parent(), Some(build_id));
+        assert!(matches!(graph.restore(compile_id), Err(GraphError::TrashedTaskNotFound)));
+
+        graph.trash(docs_id, 0).unwrap();
+        assert_eq!(graph.empty_trash(), 1);
+        assert!(graph.trashed().is_empty());
+    }
+}
@@ -0,0 +1,427 @@
+use std::time::Duration;
+use chrono::Local;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+use tdc::{Config, Graph, GraphError, Task, TaskId, TaskOrder, TaskStatus};
+use crate::{todo_rows, AppError, Result, TaskIds, TaskTree};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const HELP: &str = "q quit | tab pane | a add | e rename | space select | x done | p progress | w waiting | u todo | X remove | t/T tag | o order | d/D dep";
+
+/// Runs the terminal UI until the user quits.
+/// Every change is saved to the graph file immediately, as with the CLI commands.
+pub fn run(config: Config, graph: Graph) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let result = Tui::new(config, graph).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// Pane that receives navigation keys.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Pane {
+    Tasks,
+    Todo,
+}
+
+/// Action awaiting text input from the prompt line.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Prompt {
+    Add,
+    Rename(TaskId),
+    TagAdd(TaskId),
+    TagRemove(TaskId),
+    Order(TaskId),
+    DepAdd(TaskId),
+    DepRemove(TaskId),
+}
+
+impl Prompt {
+    fn label(self) -> &'static str {
+        match self {
+            Prompt::Add => "New task name",
+            Prompt::Rename(_) => "New name",
+            Prompt::TagAdd(_) => "Tags to add",
+            Prompt::TagRemove(_) => "Tags to remove",
+            Prompt::Order(_) => "Order (empty to clear)",
+            Prompt::DepAdd(_) => "Dependency ids to add",
+            Prompt::DepRemove(_) => "Dependency ids to remove",
+        }
+    }
+}
+
+struct Tui {
+    config: Config,
+    graph: Graph,
+    pane: Pane,
+    tasks_state: TableState,
+    todo_state: TableState,
+    prompt: Option<(Prompt, String)>,
+    message: Option<String>,
+    quit: bool,
+}
+
+impl Tui {
+
+    fn new(config: Config, graph: Graph) -> Self {
+        Self {
+            config,
+            graph,
+            pane: Pane::Tasks,
+            tasks_state: TableState::default().with_selected(0),
+            todo_state: TableState::default().with_selected(0),
+            prompt: None,
+            message: None,
+            quit: false,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(POLL_INTERVAL)? { continue }
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press { continue }
+                if let Err(err) = self.handle_key(key) {
+                    self.message = Some(format!("Error: {err}"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Ids of tasks in the task list pane, in display order.
+    fn task_ids(&self) -> Vec<TaskId> {
+        let mut tasks: Vec<(TaskId, &Task)> = self.graph.iter().collect();
+        tasks.sort_by_key(|(task_id, task)| (!task.selected, *task_id));
+        tasks.into_iter().map(|(task_id, _)| task_id).collect()
+    }
+
+    /// Ids of tasks in the todo pane, in display order.
+    fn todo_ids(&self) -> Vec<TaskId> {
+        todo_rows(&self.graph, true).into_iter().map(|task_row| task_row.id).collect()
+    }
+
+    /// Task highlighted in the focused pane.
+    fn current_task(&self) -> Option<TaskId> {
+        let (ids, state) = match self.pane {
+            Pane::Tasks => (self.task_ids(), &self.tasks_state),
+            Pane::Todo => (self.todo_ids(), &self.todo_state),
+        };
+        state.selected().and_then(|index| ids.get(index).copied())
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
+        if let Some((prompt, mut input)) = self.prompt.take() {
+            match key.code {
+                KeyCode::Enter => self.submit(prompt, &input)?,
+                KeyCode::Esc => {},
+                KeyCode::Backspace => { input.pop(); self.prompt = Some((prompt, input)) },
+                KeyCode::Char(c) => { input.push(c); self.prompt = Some((prompt, input)) },
+                _ => self.prompt = Some((prompt, input)),
+            }
+            return Ok(());
+        }
+        self.message = None;
+        let current = self.current_task();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.pane = match self.pane {
+                    Pane::Tasks => Pane::Todo,
+                    Pane::Todo => Pane::Tasks,
+                };
+            },
+            KeyCode::Down | KeyCode::Char('j') => self.state_mut().select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.state_mut().select_previous(),
+            KeyCode::Home | KeyCode::Char('g') => self.state_mut().select_first(),
+            KeyCode::End | KeyCode::Char('G') => self.state_mut().select_last(),
+            KeyCode::Char('a') => self.prompt = Some((Prompt::Add, String::new())),
+            KeyCode::Char(c) => {
+                let Some(task_id) = current else { return Ok(()) };
+                let prompt = match c {
+                    ' ' => {
+                        let selected = self.graph.get(task_id).ok_or(GraphError::TaskNotFound)?.selected;
+                        self.graph.set_selected(task_id, !selected)?;
+                        return self.save();
+                    },
+                    'x' | 'p' | 'w' | 'u' => {
+                        let status = match c {
+                            'x' => TaskStatus::Done,
+                            'p' => TaskStatus::InProgress,
+                            'w' => TaskStatus::Blocked,
+                            _ => TaskStatus::Todo,
+                        };
+                        self.graph.set_status(task_id, status, Local::now().timestamp())?;
+                        self.message = Some(format!("Marked task {task_id} as {status}"));
+                        return self.save();
+                    },
+                    'X' => {
+                        self.graph.trash(task_id, Local::now().timestamp())?;
+                        self.message = Some(format!("Moved task {task_id} to the trash"));
+                        return self.save();
+                    },
+                    'e' => Prompt::Rename(task_id),
+                    't' => Prompt::TagAdd(task_id),
+                    'T' => Prompt::TagRemove(task_id),
+                    'o' => Prompt::Order(task_id),
+                    'd' => Prompt::DepAdd(task_id),
+                    'D' => Prompt::DepRemove(task_id),
+                    _ => return Ok(()),
+                };
+                let input = match prompt {
+                    Prompt::Rename(task_id) => self.graph.get(task_id).map(|task| task.name.clone()).unwrap_or_default(),
+                    _ => String::new(),
+                };
+                self.prompt = Some((prompt, input));
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn state_mut(&mut self) -> &mut TableState {
+        match self.pane {
+            Pane::Tasks => &mut self.tasks_state,
+            Pane::Todo => &mut self.todo_state,
+        }
+    }
+
+    /// Applies a prompt's input to the graph.
+    fn submit(&mut self, prompt: Prompt, input: &str) -> Result<()> {
+        let input = input.trim();
+        match prompt {
+            Prompt::Add => {
+                if input.is_empty() { return Ok(()) }
+                let mut task = Task::new(input);
+                task.created_at = Some(Local::now().timestamp());
+                let task_id = self.graph.insert(task);
+                self.message = Some(format!("Added task {task_id}"));
+            },
+            Prompt::Rename(task_id) => {
+                if input.is_empty() { return Ok(()) }
+                let task = self.graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
+                task.name = input.to_owned();
+            },
+            Prompt::TagAdd(task_id) => {
+                let task = self.graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
+                for tag in input.split_whitespace() {
+                    task.add_tag(tag.to_owned());
+                }
+            },
+            Prompt::TagRemove(task_id) => {
+                let task = self.graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
+                for tag in input.split_whitespace() {
+                    task.remove_tag(tag);
+                }
+            },
+            Prompt::Order(task_id) => {
+                let order: TaskOrder = input.parse()?;
+                let task = self.graph.get_mut(task_id).ok_or(GraphError::TaskNotFound)?;
+                task.order = order;
+            },
+            Prompt::DepAdd(task_id) => {
+                for dependency_id in self.parse_task_ids(input)? {
+                    self.graph.insert_dependency(task_id, dependency_id)?;
+                }
+            },
+            Prompt::DepRemove(task_id) => {
+                for dependency_id in self.parse_task_ids(input)? {
+                    self.graph.remove_dependency(task_id, dependency_id)?;
+                }
+            },
+        }
+        self.save()
+    }
+
+    /// Parses whitespace-separated task ids and ranges.
+    fn parse_task_ids(&self, input: &str) -> Result<Vec<TaskId>> {
+        let mut result = vec![];
+        for token in input.split_whitespace() {
+            let task_ids: TaskIds = token.parse().map_err(|_| AppError::InvalidTaskId(token.to_owned()))?;
+            result.extend(task_ids.expand(&self.graph));
+        }
+        Ok(result)
+    }
+
+    fn save(&mut self) -> Result<()> {
+        self.graph.save(&self.config)?;
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main_area, status_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [tasks_area, side_area] = Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(main_area);
+        let [todo_area, tree_area] = Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(side_area);
+        self.draw_tasks(frame, tasks_area);
+        self.draw_todo(frame, todo_area);
+        self.draw_tree(frame, tree_area);
+        self.draw_status(frame, status_area);
+    }
+
+    fn draw_tasks(&mut self, frame: &mut Frame, area: Rect) {
+        let rows: Vec<Row> = self.task_ids()
+            .into_iter()
+            .map(|task_id| {
+                let task = self.graph.get(task_id).unwrap();
+                let selected = if task.selected { "*" } else { "" };
+                let dependencies: Vec<String> = task.dependencies().iter().map(|id| id.to_string()).collect();
+                Row::new(vec![
+                    task_id.to_string(),
+                    selected.to_owned(),
+                    task.status.to_string(),
+                    task.name.clone(),
+                    task.tags().join(","),
+                    task.order.to_string(),
+                    dependencies.join(","),
+                ])
+            })
+            .collect();
+        let header = Row::new(["id", "sel", "status", "name", "tags", "order", "deps"]).bold();
+        let widths = [
+            Constraint::Length(4),
+            Constraint::Length(3),
+            Constraint::Length(11),
+            Constraint::Fill(3),
+            Constraint::Fill(1),
+            Constraint::Length(6),
+            Constraint::Fill(1),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(pane_block("Tasks", self.pane == Pane::Tasks))
+            .row_highlight_style(highlight_style(self.pane == Pane::Tasks));
+        frame.render_stateful_widget(table, area, &mut self.tasks_state);
+    }
+
+    fn draw_todo(&mut self, frame: &mut Frame, area: Rect) {
+        let rows: Vec<Row> = todo_rows(&self.graph, true)
+            .into_iter()
+            .map(|task_row| {
+                let style = if task_row.doable { Style::default().fg(Color::Yellow) } else { Style::default().dim() };
+                Row::new(vec![task_row.id.to_string(), task_row.name.to_string(), task_row.order.to_string()]).style(style)
+            })
+            .collect();
+        let widths = [Constraint::Length(4), Constraint::Fill(1), Constraint::Length(6)];
+        let table = Table::new(rows, widths)
+            .block(pane_block("Todo", self.pane == Pane::Todo))
+            .row_highlight_style(highlight_style(self.pane == Pane::Todo));
+        frame.render_stateful_widget(table, area, &mut self.todo_state);
+    }
+
+    fn draw_tree(&self, frame: &mut Frame, area: Rect) {
+        let tree = self.current_task()
+            .and_then(|task_id| Some(TaskTree { task_id, task: self.graph.get(task_id)?, graph: &self.graph }))
+            .map(|task_tree| task_tree.to_string())
+            .unwrap_or_default();
+        let paragraph = Paragraph::new(tree).block(pane_block("Dependencies", false));
+        frame.render_widget(paragraph, area);
+    }
+
+    fn draw_status(&self, frame: &mut Frame, area: Rect) {
+        let line = match (&self.prompt, &self.message) {
+            (Some((prompt, input)), _) => Line::from(vec![
+                Span::styled(format!("{}: ", prompt.label()), Style::default().bold()),
+                Span::raw(input.as_str()),
+                Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+            ]),
+            (None, Some(message)) => Line::raw(message.as_str()),
+            (None, None) => Line::raw(HELP).dim(),
+        };
+        frame.render_widget(Paragraph::new(line), area);
+    }
+}
+
+fn pane_block(title: &str, focused: bool) -> Block<'_> {
+    let border_style = if focused { Style::default().fg(Color::Cyan) } else { Style::default() };
+    Block::default().title(title).borders(Borders::ALL).border_style(border_style)
+}
+
+fn highlight_style(focused: bool) -> Style {
+    if focused { Style::default().reversed() } else { Style::default() }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+    use tdc::{Config, Graph, TaskStatus};
+    use crate::Result;
+    use super::{Pane, Prompt, Tui};
+
+    fn press(tui: &mut Tui, code: KeyCode) -> Result<()> {
+        tui.handle_key(KeyEvent::from(code))
+    }
+
+    fn type_text(tui: &mut Tui, text: &str) {
+        for c in text.chars() {
+            press(tui, KeyCode::Char(c)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_keys_and_prompts() {
+        let root = env::temp_dir().join(format!("tdc_test_keys_and_prompts_{}", std::process::id()));
+        let graph_path = root.join("graph.ron");
+        let config = Config::load_with(Some(&root.join("config.ron")), Some(&graph_path), &root).unwrap();
+        let mut tui = Tui::new(config, Graph::new());
+
+        // Adding tasks, with editing and cancelling of the prompt
+        press(&mut tui, KeyCode::Char('a')).unwrap();
+        assert_eq!(tui.prompt, Some((Prompt::Add, String::new())));
+        type_text(&mut tui, "Shipp");
+        press(&mut tui, KeyCode::Backspace).unwrap();
+        press(&mut tui, KeyCode::Enter).unwrap();
+        assert_eq!(tui.prompt, None);
+        assert_eq!(tui.message.as_deref(), Some("Added task 0"));
+        assert_eq!(tui.graph.get(0).unwrap().name, "Ship");
+        press(&mut tui, KeyCode::Char('a')).unwrap();
+        type_text(&mut tui, "Build");
+        press(&mut tui, KeyCode::Esc).unwrap();
+        assert_eq!(tui.prompt, None);
+        assert_eq!(tui.graph.len(), 1);
+        press(&mut tui, KeyCode::Char('a')).unwrap();
+        type_text(&mut tui, "Build");
+        press(&mut tui, KeyCode::Enter).unwrap();
+
+        // Rename starts from the current name, and changes are saved
+        press(&mut tui, KeyCode::Char('e')).unwrap();
+        assert_eq!(tui.prompt, Some((Prompt::Rename(0), "Ship".to_owned())));
+        type_text(&mut tui, " it");
+        press(&mut tui, KeyCode::Enter).unwrap();
+        assert_eq!(tui.graph.get(0).unwrap().name, "Ship it");
+        assert_eq!(Graph::load_file(&graph_path).unwrap().get(0).unwrap().name, "Ship it");
+
+        // Dependencies, selection and the todo pane
+        press(&mut tui, KeyCode::Char('d')).unwrap();
+        type_text(&mut tui, "1");
+        press(&mut tui, KeyCode::Enter).unwrap();
+        assert_eq!(tui.graph.get(0).unwrap().dependencies(), &[1]);
+        press(&mut tui, KeyCode::Char(' ')).unwrap();
+        assert!(tui.graph.get(0).unwrap().selected);
+        press(&mut tui, KeyCode::Tab).unwrap();
+        assert_eq!(tui.pane, Pane::Todo);
+        assert_eq!(tui.current_task(), Some(1));
+        press(&mut tui, KeyCode::Char('x')).unwrap();
+        assert_eq!(tui.graph.get(1).unwrap().status, TaskStatus::Done);
+        assert_eq!(tui.message.as_deref(), Some("Marked task 1 as done"));
+
+        // Invalid input is reported as an error and closes the prompt
+        press(&mut tui, KeyCode::BackTab).unwrap();
+        assert_eq!(tui.pane, Pane::Tasks);
+        press(&mut tui, KeyCode::Char('o')).unwrap();
+        type_text(&mut tui, "soon");
+        assert!(press(&mut tui, KeyCode::Enter).is_err());
+        assert_eq!(tui.prompt, None);
+
+        press(&mut tui, KeyCode::Char('q')).unwrap();
+        assert!(tui.quit);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
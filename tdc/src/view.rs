@@ -0,0 +1,142 @@
+use serde::{Serialize, Deserialize};
+use crate::{Graph, Task, TaskId, TaskStatus};
+
+/// A named combination of filters, sorting and columns, stored in the [`Config`](crate::Config).
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Default, Debug)]
+pub struct View {
+    /// Tasks the view starts from.
+    #[serde(default)]
+    pub source: ViewSource,
+    /// Only tasks having all of these tags (or their sub-tags) are shown.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Hides tasks that are not doable, ie: blocked or waiting on unfinished dependencies.
+    #[serde(default)]
+    pub hide_blocked: bool,
+    #[serde(default)]
+    pub sort: ViewSort,
+    /// Columns shown by the CLI, in order. All columns are shown if empty.
+    #[serde(default)]
+    pub columns: Vec<Column>,
+}
+
+impl View {
+
+    /// Tasks in the view, filtered and sorted.
+    pub fn apply<'a>(&self, graph: &'a Graph) -> Vec<(TaskId, &'a Task)> {
+        let tasks: Vec<(TaskId, &Task)> = match self.source {
+            ViewSource::Todo => graph
+                .traverse_selected()
+                .into_iter()
+                .filter(|(_, task)| !task.status.is_finished())
+                .collect(),
+            ViewSource::All => graph.iter().collect(),
+        };
+        let mut tasks: Vec<(TaskId, &Task)> = tasks
+            .into_iter()
+            .filter(|(_, task)| task.has_all_tags(&self.tags))
+            .filter(|(task_id, _)| !self.hide_blocked || graph.is_doable(*task_id))
+            .collect();
+        match self.sort {
+            ViewSort::Default => match self.source {
+                ViewSource::Todo => tasks.sort_by_key(|(task_id, task)| (
+                    !graph.is_doable(*task_id),
+                    graph.waits_on_soft_dependencies(*task_id),
+                    task.status != TaskStatus::InProgress,
+                    task.order,
+                )),
+                ViewSource::All => tasks.sort_by_key(|(_, task)| !task.selected),
+            },
+            ViewSort::Id => tasks.sort_by_key(|(task_id, _)| *task_id),
+            ViewSort::Name => tasks.sort_by_key(|(_, task)| task.name.to_lowercase()),
+            ViewSort::Order => tasks.sort_by_key(|(task_id, task)| (task.order, *task_id)),
+        }
+        tasks
+    }
+}
+
+/// Tasks a [`View`] starts from.
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum ViewSource {
+    /// Selected tasks and their dependencies, like `tdc todo`.
+    #[default]
+    Todo,
+    /// Every task, like `tdc ls`.
+    All,
+}
+
+/// Sorting of tasks in a [`View`].
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub enum ViewSort {
+    /// Same sorting as the command matching the view's source.
+    #[default]
+    Default,
+    Id,
+    Name,
+    Order,
+}
+
+/// Column of a task table.
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Column {
+    Id,
+    Name,
+    Tags,
+    Status,
+    Selected,
+    Order,
+    Dependencies,
+}
+
+impl Column {
+    /// Header of the column in task tables.
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Name => "name",
+            Column::Tags => "tags",
+            Column::Status => "status",
+            Column::Selected => "selected",
+            Column::Order => "order",
+            Column::Dependencies => "dependencies",
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{Graph, Task, TaskOrder, View, ViewSort, ViewSource};
+
+    #[test]
+    fn test_view_filters_and_sorts() {
+        let mut graph = Graph::new();
+        let mut deploy = Task::new("Deploy");
+        deploy.add_tag("work".into());
+        deploy.selected = true;
+        let deploy_id = graph.insert(deploy);
+        let mut build = Task::new("Build");
+        build.add_tag("work/ci".into());
+        build.order = TaskOrder::Order(10);
+        let build_id = graph.insert(build);
+        let mut test = Task::new("Test");
+        test.add_tag("work".into());
+        test.order = TaskOrder::Order(5);
+        let test_id = graph.insert(test);
+        graph.insert(Task::new("Groceries"));
+        graph.insert_dependency(deploy_id, build_id).unwrap();
+        graph.insert_dependency(deploy_id, test_id).unwrap();
+
+        let view = View { tags: vec!["work".into()], sort: ViewSort::Order, ..View::default() };
+        let ids: Vec<_> = view.apply(&graph).into_iter().map(|(task_id, _)| task_id).collect();
+        assert_eq!(ids, &[test_id, build_id, deploy_id]);
+
+        let view = View { hide_blocked: true, ..view };
+        let ids: Vec<_> = view.apply(&graph).into_iter().map(|(task_id, _)| task_id).collect();
+        assert_eq!(ids, &[test_id, build_id]);
+
+        let view = View { source: ViewSource::All, sort: ViewSort::Name, ..View::default() };
+        let names: Vec<_> = view.apply(&graph).into_iter().map(|(_, task)| task.name.as_str()).collect();
+        assert_eq!(names, &["Build", "Deploy", "Groceries", "Test"]);
+    }
+}
@@ -3,15 +3,30 @@ use rand::prelude::*;
 use bevy::prelude::*;
 use bevy::text::TextBounds;
 use std::collections::HashMap;
-use tdc::TaskId;
+use tdc::{Task, TaskId, TaskStatus};
 pub use action::*;
 
 use crate::MainCamera;
 
 const TASK_COLOR: Color             = Color::srgb(0.1, 0.3, 0.5);
 const TASK_SELECTED_COLOR: Color    = Color::srgb(0.1, 0.6, 0.3);
+const TASK_IN_PROGRESS_COLOR: Color = Color::srgb(0.7, 0.5, 0.1);
+const TASK_BLOCKED_COLOR: Color     = Color::srgb(0.6, 0.15, 0.15);
+const TASK_DONE_COLOR: Color        = Color::srgb(0.25, 0.25, 0.25);
+const TASK_CANCELLED_COLOR: Color   = Color::srgb(0.15, 0.15, 0.15);
 const TASK_NODE_SIZE: Vec2          = Vec2::new(7.0 * GRID_SIZE, 2.0 * GRID_SIZE);
+const BADGE_COLOR: Color            = Color::srgb(0.8, 0.2, 0.5);
+const BADGE_SIZE: Vec2              = Vec2::new(1.5 * GRID_SIZE, 0.8 * GRID_SIZE);
+const BADGE_OFFSET: Vec3            = Vec3::new(TASK_NODE_SIZE.x / 2.0, TASK_NODE_SIZE.y / 2.0, 0.0002);
+/// Number of tasks unblocking the most work that get a badge.
+const BADGE_COUNT: usize            = 3;
 const LINE_COLOR: Color             = Color::srgb(0.8, 0.5, 0.2);
+const RELATED_LINE_COLOR: Color     = Color::srgba(0.6, 0.6, 0.8, 0.5);
+/// Length of the dashes of soft dependency arrows, and of the gaps between them.
+const DASH_LENGTH: f32              = 8.0;
+const FRAME_COLOR: Color            = Color::srgba(0.6, 0.8, 1.0, 0.3);
+/// Space between a group frame and the children inside it.
+const FRAME_MARGIN: f32             = GRID_SIZE / 2.0;
 const GRID_COLOR: Color             = Color::srgba(1.0, 1.0, 1.0, 0.02);
 const GRID_SIZE: f32                = 30.0;
 const MIN_X: f32    = -500.0;
@@ -35,8 +50,8 @@ impl Plugin for GraphPlugin {
         app.init_resource::<TaskMapping>();
         app.add_observer(spawn_graph);
         app.add_systems(Update, (
-            sync_task_xy,
-            (draw_arrows_between_nodes, draw_grid), 
+            (sync_task_xy, sync_collapsed_visibility),
+            (draw_arrows_between_nodes, draw_group_frames, draw_grid), 
         ));
     }
 }
@@ -97,6 +112,19 @@ mod action {
     pub struct SpawnGraph;
 } 
 
+/// Color of a task node, based on its status.
+/// Selected tasks that are still to do stand out from the rest.
+fn task_color(task: &Task) -> Color {
+    match task.status {
+        TaskStatus::Todo if task.selected => TASK_SELECTED_COLOR,
+        TaskStatus::Todo => TASK_COLOR,
+        TaskStatus::InProgress => TASK_IN_PROGRESS_COLOR,
+        TaskStatus::Blocked => TASK_BLOCKED_COLOR,
+        TaskStatus::Done => TASK_DONE_COLOR,
+        TaskStatus::Cancelled => TASK_CANCELLED_COLOR,
+    }
+}
+
 /// Spawns graph + tasks when triggered.
 /// Used at application startup.
 fn spawn_graph(
@@ -107,9 +135,17 @@ fn spawn_graph(
 ) {
     let mut task_mapping = TaskMapping::default();
     let mut z = 0.0;
+    // Tasks unblocking the most work get a badge with the number of tasks they unblock
+    let badges: HashMap<TaskId, usize> = info.graph
+        .bottlenecks(false)
+        .into_iter()
+        .filter(|bottleneck| !bottleneck.unblocked.is_empty())
+        .take(BADGE_COUNT)
+        .map(|bottleneck| (bottleneck.task_id, bottleneck.unblocked.len()))
+        .collect();
     for (task_id, task) in info.graph.iter() {
         let (x, y) = get_task_position(task.xy);
-        let color = if !task.selected { TASK_COLOR } else { TASK_SELECTED_COLOR };
+        let color = task_color(task);
         let task_e = commands.spawn((
             Sprite::from_color(color, TASK_NODE_SIZE),
             TaskNode { task_id },
@@ -122,9 +158,22 @@ fn spawn_graph(
         ))
         .observe(translate_on_drag)
         .observe(round_on_drag_end)
+        .observe(toggle_collapsed_on_click)
         .observe(pointer_on_over)
         .observe(default_on_out)
         .id();
+        if let Some(unblocked) = badges.get(&task_id) {
+            commands.entity(task_e).with_children(|parent| {
+                parent.spawn((
+                    Sprite::from_color(BADGE_COLOR, BADGE_SIZE),
+                    Transform::from_translation(BADGE_OFFSET),
+                )).with_child((
+                    Text2d(format!("+{unblocked}")),
+                    Transform::from_translation(Vec3::new(0.0, 0.0, 0.0001)),
+                    gui_assets.task_font.clone(),
+                ));
+            });
+        }
         task_mapping.insert(task_id, task_e);
         z += 1.0;
     }
@@ -144,30 +193,83 @@ fn get_task_position(pos: Option<(f32, f32)>) -> (f32, f32) {
 }
 
 fn draw_arrows_between_nodes(
-    task_nodes: Query<(&TaskNode, &Transform)>,
+    task_nodes: Query<(&TaskNode, &Transform, &Visibility)>,
     task_mapping: Res<TaskMapping>,
     info: ResMut<GraphInfo>,
     mut draw: Gizmos,
 ) {
     let task_half_size = TASK_NODE_SIZE / 2.0;
-    for (node, node_transf)  in &task_nodes {
+    for (node, node_transf, node_visibility) in &task_nodes {
+        if *node_visibility == Visibility::Hidden { continue }
         let task = info.graph.get(node.task_id).unwrap();
         let task_min = node_transf.translation.xy() - task_half_size; 
         let task_max = node_transf.translation.xy() + task_half_size; 
         for dep_task_id in task.dependencies() {
             let dep_task_entity = task_mapping.get_entity(*dep_task_id).unwrap();
-            let (_dep_node, dep_node_transf) = task_nodes.get(dep_task_entity).unwrap();
+            let (_dep_node, dep_node_transf, dep_node_visibility) = task_nodes.get(dep_task_entity).unwrap();
+            if *dep_node_visibility == Visibility::Hidden { continue }
             let dep_min = dep_node_transf.translation.xy() - task_half_size; 
             let dep_max = dep_node_transf.translation.xy() + task_half_size; 
             let line_start = node_transf.translation.xy();
             let line_end = dep_node_transf.translation.xy();
             let line_start = trim_line_on_box(line_end, line_start, task_min, task_max);
             let line_end = trim_line_on_box(line_start, line_end, dep_min, dep_max);
-            draw.arrow_2d(line_start, line_end, LINE_COLOR);
+            match task.dependency_kind(*dep_task_id) {
+                tdc::DependencyKind::Hard => { draw.arrow_2d(line_start, line_end, LINE_COLOR); },
+                tdc::DependencyKind::Soft => draw_dashed_arrow(&mut draw, line_start, line_end, LINE_COLOR),
+                tdc::DependencyKind::Related => draw.line_2d(line_start, line_end, RELATED_LINE_COLOR),
+            }
         }
     }
 }
 
+fn draw_dashed_arrow(draw: &mut Gizmos, start: Vec2, end: Vec2, color: Color) {
+    let length = start.distance(end);
+    let direction = (end - start).normalize_or_zero();
+    let mut dash_start = 0.0;
+    while dash_start + 2.0 * DASH_LENGTH < length {
+        let dash_end = dash_start + DASH_LENGTH;
+        draw.line_2d(start + direction * dash_start, start + direction * dash_end, color);
+        dash_start = dash_end + DASH_LENGTH;
+    }
+    draw.arrow_2d(start + direction * dash_start.min(length), end, color);
+}
+
+/// Draws a frame around the children of each parent task.
+/// Collapsed parents get a frame around themselves instead, as their children are hidden.
+fn draw_group_frames(
+    task_nodes: Query<(&TaskNode, &Transform, &Visibility)>,
+    task_mapping: Res<TaskMapping>,
+    info: Res<GraphInfo>,
+    mut draw: Gizmos,
+) {
+    let task_half_size = TASK_NODE_SIZE / 2.0;
+    for (node, node_transf, node_visibility) in &task_nodes {
+        if *node_visibility == Visibility::Hidden { continue }
+        let task = info.graph.get(node.task_id).unwrap();
+        if info.graph.children(node.task_id).next().is_none() { continue }
+        let (min, max) = if task.collapsed {
+            (node_transf.translation.xy() - task_half_size, node_transf.translation.xy() + task_half_size)
+        }
+        else {
+            let mut min = Vec2::splat(f32::INFINITY);
+            let mut max = Vec2::splat(f32::NEG_INFINITY);
+            for descendant_id in info.graph.descendants(node.task_id) {
+                let Some(descendant_e) = task_mapping.get_entity(descendant_id) else { continue };
+                let Ok((_, descendant_transf, descendant_visibility)) = task_nodes.get(descendant_e) else { continue };
+                if *descendant_visibility == Visibility::Hidden { continue }
+                min = min.min(descendant_transf.translation.xy() - task_half_size);
+                max = max.max(descendant_transf.translation.xy() + task_half_size);
+            }
+            if min.x > max.x { continue }
+            (min, max)
+        };
+        let min = min - FRAME_MARGIN;
+        let max = max + FRAME_MARGIN;
+        draw.rect_2d(Isometry2d::from_translation((min + max) / 2.0), max - min, FRAME_COLOR);
+    }
+}
+
 fn draw_grid(
     camera: Single<(&Transform, &OrthographicProjection), With<MainCamera>>,
     mut draw: Gizmos,
@@ -215,6 +317,36 @@ fn round_on_drag_end(
     transf.translation = new_translation.extend(0.0);
 }
 
+/// Collapses or expands the children of a task on right click.
+fn toggle_collapsed_on_click(
+    trigger: Trigger<Pointer<Click>>,
+    task_nodes: Query<&TaskNode>,
+    mut info: ResMut<GraphInfo>,
+) {
+    let (entity, event) = (trigger.entity(), trigger.event());
+    if event.button != PointerButton::Secondary { return };
+    let Ok(node) = task_nodes.get(entity) else { return };
+    if info.graph.children(node.task_id).next().is_none() { return }
+    let task = info.graph.get_mut(node.task_id).unwrap();
+    task.collapsed = !task.collapsed;
+}
+
+/// Hides the nodes of tasks having a collapsed ancestor.
+fn sync_collapsed_visibility(
+    info: Res<GraphInfo>,
+    mut task_nodes: Query<(&TaskNode, &mut Visibility)>,
+) {
+    if !info.is_changed() { return }
+    for (node, mut visibility) in &mut task_nodes {
+        let collapsed = info.graph
+            .ancestors(node.task_id)
+            .into_iter()
+            .any(|ancestor_id| info.graph.get(ancestor_id).is_some_and(|ancestor| ancestor.collapsed));
+        let new_visibility = if collapsed { Visibility::Hidden } else { Visibility::Inherited };
+        visibility.set_if_neq(new_visibility);
+    }
+}
+
 fn sync_task_xy(
     mut info: ResMut<GraphInfo>,
     mut tasks: Query<(&TaskNode, &mut Transform), Changed<Transform>>,
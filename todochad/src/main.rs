@@ -7,9 +7,23 @@ use graph::*;
 use ui::*;
 use camera::*;
 
+use std::path::PathBuf;
 use bevy::prelude::*;
+use clap::Parser;
 use thiserror::*;
 
+#[derive(Parser, Debug)]
+#[command(name="todochad")]
+#[command(about="A graphical todo list generator using a dependency graph")]
+struct Cli {
+    #[clap(help="Directory to look for a project graph in. Defaults to the current directory")]
+    dir: Option<PathBuf>,
+    #[clap(long, help="Path of the config file. Overrides $TDC_CONFIG")]
+    config: Option<PathBuf>,
+    #[clap(long, help="Path of the graph file. Overrides $TDC_GRAPH and the config file")]
+    graph: Option<PathBuf>,
+}
+
 fn main() {
     if let Err(err) = run() {
         eprintln!("Error: {err}");
@@ -18,7 +32,12 @@ fn main() {
 }
 
 fn run() -> Result<()> {
-    let config = tdc::Config::load()?;
+    let cli = Cli::parse();
+    let project_dir = match cli.dir {
+        Some(dir) => dir,
+        None => std::env::current_dir()?,
+    };
+    let config = tdc::Config::load_with(cli.config.as_deref(), cli.graph.as_deref(), &project_dir)?;
     let graph = tdc::Graph::load(&config)?;
     App::new()
         .add_plugins((
@@ -46,6 +65,8 @@ pub enum AppError {
     ConfigError(#[from] tdc::ConfigError),
     #[error(transparent)]
     GraphError(#[from] tdc::GraphError),
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
 }
 
 type Result<T> = std::result::Result<T, AppError>;
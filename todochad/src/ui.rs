@@ -20,6 +20,15 @@ pub struct LeftPanel {
 #[require(Node)]
 pub struct RightPanel {
     todo_task_infos: Vec<TaskInfo>,
+    /// Name of the view from the config file being shown. The plain todo list is shown if none.
+    view: Option<String>,
+}
+
+/// Task shown in the todo list of the right panel.
+/// Dropping one onto another moves it to that spot in the order.
+#[derive(Component, Debug)]
+struct TodoItem {
+    task_id: tdc::TaskId,
 }
 
 #[derive(Debug)]
@@ -108,9 +117,9 @@ fn spawn_right_panel(
     info: Res<GraphInfo>,
     mut commands: Commands,
 ) {
-    let todo_task_infos = generate_task_infos(&info.graph);
+    let todo_task_infos = generate_task_infos(&info.graph, None);
     commands.spawn((
-        RightPanel { todo_task_infos },
+        RightPanel { todo_task_infos, view: None },
         BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
         Node {
             flex_direction: FlexDirection::Column,
@@ -130,12 +139,37 @@ fn render_right_panel(
     info: Res<GraphInfo>,
 ) {
     let Some((panel_e, panel)) = right_panel_q.iter_mut().next() else { return };
+    commands.entity(panel_e).despawn_descendants();
     let s = &mut Spawner::relative(panel_e, &mut commands);
     let header_font = &gui_assets.ui_header_font;
     let font = &gui_assets.ui_font;
+    let mut view_buttons = vec![];
+    let mut todo_items = vec![];
     NodeW::new().cfg(cfg::side_panel).begin(s);
 
-        TextW::new("Todo List").config(cfg::header, header_font).insert(s);
+        // View group
+        if !info.config.views.is_empty() {
+            TextW::new("Views").config(cfg::header, header_font).insert(s);
+            NodeW::new().cfg(cfg::view_group).begin(s);
+                let view_names = std::iter::once(None).chain(info.config.views.keys().cloned().map(Some));
+                for view_name in view_names {
+                    let label = view_name.as_deref().unwrap_or("Todo").to_owned();
+                    if view_name == panel.view {
+                        ButtonW::new().cfg(cfg::button_primary).begin(s);
+                    }
+                    else {
+                        ButtonW::new().cfg(cfg::button_secondary).begin(s);
+                    }
+                        view_buttons.push((s.last(), view_name));
+                        TextW::new(label).config(cfg::text, font).insert(s);
+                    ButtonW::end(s);
+                }
+            NodeW::end(s);
+        }
+
+        // Task group
+        let title = panel.view.as_deref().unwrap_or("Todo List");
+        TextW::new(title).config(cfg::header, header_font).insert(s);
         NodeW::new().cfg(cfg::group).begin(s);
         for task_info in panel.todo_task_infos.iter() {
             let task = info.graph.get(task_info.task_id).unwrap();
@@ -145,9 +179,66 @@ fn render_right_panel(
             else {
                 TextW::new(&task.name).config(cfg::todo_disabled_text, font).insert(s);
             }
+            todo_items.push((s.last(), task_info.task_id));
         }
         NodeW::end(s);
     NodeW::end(s);
+
+    // Callbacks
+    for (button_e, view_name) in view_buttons {
+        commands
+            .entity(button_e)
+            .observe(pointer_on_over)
+            .observe(default_on_out)
+            .observe(switch_view_on_press(view_name));
+    }
+    for (todo_e, task_id) in todo_items {
+        commands
+            .entity(todo_e)
+            .insert(TodoItem { task_id })
+            .observe(pointer_on_over)
+            .observe(default_on_out)
+            .observe(reorder_on_drop);
+    }
+}
+
+/// Moves the todo item dropped onto another one to its spot, using the same ordering as `tdc order --before/--after`.
+fn reorder_on_drop(
+    trigger: Trigger<Pointer<DragDrop>>,
+    todo_item_q: Query<&TodoItem>,
+    mut info: ResMut<GraphInfo>,
+    mut right_panel_q: Query<&mut RightPanel>,
+) {
+    let event = trigger.event();
+    if event.button != PointerButton::Primary { return };
+    let (Ok(target), Ok(dropped)) = (todo_item_q.get(trigger.entity()), todo_item_q.get(event.dropped)) else { return };
+    let Ok(mut panel) = right_panel_q.get_single_mut() else { return };
+    let index_of = |task_id| panel.todo_task_infos.iter().position(|task_info| task_info.task_id == task_id);
+    // Items dragged down land after the target, and items dragged up land before it
+    let position = if index_of(dropped.task_id) < index_of(target.task_id) {
+        tdc::OrderPosition::After(target.task_id)
+    }
+    else {
+        tdc::OrderPosition::Before(target.task_id)
+    };
+    // The dragged task may be gone, ie: after the graph file was reloaded
+    let Ok(_) = info.graph.reorder(dropped.task_id, position) else { return };
+    let view = panel.view.as_ref().and_then(|name| info.config.views.get(name));
+    let todo_task_infos = generate_task_infos(&info.graph, view);
+    panel.todo_task_infos = todo_task_infos;
+}
+
+/// Creates an observer that switches the right panel to a view when pressed.
+fn switch_view_on_press(
+    view_name: Option<String>,
+) -> impl Fn(Trigger<Pointer<Down>>, Res<GraphInfo>, Query<&mut RightPanel>) {
+    move |trigger, info, mut right_panel_q| {
+        if trigger.event().button != PointerButton::Primary { return };
+        let Ok(mut panel) = right_panel_q.get_single_mut() else { return };
+        let view = view_name.as_ref().and_then(|name| info.config.views.get(name));
+        panel.todo_task_infos = generate_task_infos(&info.graph, view);
+        panel.view = view_name.clone();
+    }
 }
 
 
@@ -165,17 +256,18 @@ fn save_on_press(
     graph.save(config).expect("Failed to save graph");
 }
 
-fn generate_task_infos(graph: &tdc::Graph) -> Vec<TaskInfo> {
-    let mut task_infos: Vec<TaskInfo> = graph
-        .traverse_selected()
+/// Generates the task list of the right panel using a view.
+/// Uses the todo list of selected tasks if no view is given.
+fn generate_task_infos(graph: &tdc::Graph, view: Option<&tdc::View>) -> Vec<TaskInfo> {
+    let default_view = tdc::View::default();
+    let view = view.unwrap_or(&default_view);
+    view.apply(graph)
         .into_iter()
-        .map(|(task_id, task)| TaskInfo {
+        .map(|(task_id, _)| TaskInfo {
             task_id,
-            doable: task.dependencies().is_empty(),
+            doable: graph.is_doable(task_id),
         })
-        .collect();
-    task_infos.sort_by_key(|task_info| !task_info.doable);
-    task_infos
+        .collect()
 }
 
 /// Actions that drive UI behavior.
@@ -206,6 +298,13 @@ mod cfg {
         node.node.margin = UiRect::px(15.0, 0.0, 0.0, 10.0);
     }
 
+    pub fn view_group(node: &mut NodeW) {
+        node.node.flex_direction = FlexDirection::Row;
+        node.node.flex_wrap = FlexWrap::Wrap;
+        node.node.row_gap = Val::Px(5.0);
+        node.node.margin = UiRect::px(15.0, 0.0, 0.0, 10.0);
+    }
+
     pub fn button_primary(button: &mut ButtonW) {
         button.node.justify_content = JustifyContent::Center;
         button.background_color = Color::srgb(0.1, 0.6, 0.2).into();